@@ -0,0 +1,285 @@
+use std::{fmt::Display, str::FromStr};
+
+use getset::Getters;
+
+use crate::{midi::MidiMessageType, MidiNote};
+
+/// A MIDI message which triggers a command, optionally only on a given MIDI channel (1-16)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// A controller moved to the given value, or to any value above 0 (like a button being
+    /// pressed) if no value is given
+    Controller {
+        controller: u8,
+        value: Option<u8>,
+        channel: Option<u8>,
+    },
+    /// A program change to the given program
+    Program { program: u8, channel: Option<u8> },
+    /// A note being pressed, such as from a drum pad
+    Note { note: MidiNote, channel: Option<u8> },
+}
+
+impl Trigger {
+    /// The MIDI channel the trigger is restricted to, if any
+    fn channel(&self) -> Option<u8> {
+        match self {
+            Trigger::Controller { channel, .. }
+            | Trigger::Program { channel, .. }
+            | Trigger::Note { channel, .. } => *channel,
+        }
+    }
+
+    /// Whether the given message triggers the command
+    ///
+    /// Releasing a note counts as triggering, such that the release of a bound note does not
+    /// reach the notes being entered.
+    fn matches(&self, message: &MidiMessageType) -> bool {
+        let on = |bound: &Option<u8>, channel: &u8| bound.is_none_or(|bound| bound == *channel);
+        match (self, message) {
+            (
+                Trigger::Controller {
+                    controller,
+                    value,
+                    channel,
+                },
+                MidiMessageType::ControlChange {
+                    controller: c,
+                    value: v,
+                    channel: ch,
+                }
+                | MidiMessageType::PedalOn {
+                    pedal: c,
+                    value: v,
+                    channel: ch,
+                },
+            ) => controller == c && value.map_or(*v > 0, |value| value == *v) && on(channel, ch),
+            (
+                Trigger::Controller {
+                    controller,
+                    value: Some(0),
+                    channel,
+                },
+                MidiMessageType::PedalOff { pedal, channel: ch },
+            ) => controller == pedal && on(channel, ch),
+            (
+                Trigger::Program { program, channel },
+                MidiMessageType::ProgramChange {
+                    program: p,
+                    channel: ch,
+                },
+            ) => program == p && on(channel, ch),
+            (
+                Trigger::Note { note, channel },
+                MidiMessageType::NoteOn {
+                    note: n,
+                    channel: ch,
+                    ..
+                }
+                | MidiMessageType::NoteOff {
+                    note: n,
+                    channel: ch,
+                    ..
+                },
+            ) => note == n && on(channel, ch),
+            _ => false,
+        }
+    }
+
+    /// The trigger to learn from the given message, if it comes from a controller (with a value
+    /// above 0) or is a program change
+    fn learn(message: &MidiMessageType) -> Option<Self> {
+        match message {
+            MidiMessageType::ControlChange {
+                controller,
+                value,
+                channel,
+            }
+            | MidiMessageType::PedalOn {
+                pedal: controller,
+                value,
+                channel,
+            } if *value > 0 => Some(Trigger::Controller {
+                controller: *controller,
+                value: None,
+                channel: Some(*channel),
+            }),
+            MidiMessageType::ProgramChange { program, channel } => Some(Trigger::Program {
+                program: *program,
+                channel: Some(*channel),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = BindingError;
+
+    /// Parse a trigger given as `cc<controller>[:<value>]`, `pc<program>` or `note<note>`, each
+    /// optionally followed by `@<channel>` (e.g. `cc20:127@10`, `pc3`, `note36@10`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BindingError::InvalidTriggerString(s.into());
+        let number = |n: &str| {
+            n.parse::<u8>()
+                .ok()
+                .filter(|n| *n < 128)
+                .ok_or_else(invalid)
+        };
+        let (message, channel) = match s.split_once('@') {
+            Some((message, channel)) => (
+                message,
+                Some(
+                    channel
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|channel| (1..=16).contains(channel))
+                        .ok_or_else(invalid)?,
+                ),
+            ),
+            None => (s, None),
+        };
+        if let Some(controller) = message.strip_prefix("cc") {
+            let (controller, value) = match controller.split_once(':') {
+                Some((controller, value)) => (controller, Some(number(value)?)),
+                None => (controller, None),
+            };
+            Ok(Trigger::Controller {
+                controller: number(controller)?,
+                value,
+                channel,
+            })
+        } else if let Some(program) = message.strip_prefix("pc") {
+            Ok(Trigger::Program {
+                program: number(program)?,
+                channel,
+            })
+        } else if let Some(note) = message.strip_prefix("note") {
+            Ok(Trigger::Note {
+                note: number(note)?,
+                channel,
+            })
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl Display for Trigger {
+    /// Write the trigger in the form it is parsed from
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Controller {
+                controller,
+                value: Some(value),
+                ..
+            } => write!(f, "cc{controller}:{value}")?,
+            Trigger::Controller { controller, .. } => write!(f, "cc{controller}")?,
+            Trigger::Program { program, .. } => write!(f, "pc{program}")?,
+            Trigger::Note { note, .. } => write!(f, "note{note}")?,
+        };
+        match self.channel() {
+            Some(channel) => write!(f, "@{channel}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A command which is run when a MIDI message triggers it
+///
+/// The command is either a keyswitch action (e.g. `undo`), or settings like on stdin, where
+/// several settings are separated by `;` (e.g. `mode=chord;accidentals=flats`).
+#[derive(Debug, Clone, Getters)]
+pub struct Binding {
+    #[getset(get = "pub")]
+    trigger: Trigger,
+    #[getset(get = "pub")]
+    command: String,
+}
+
+impl Display for Binding {
+    /// Write the binding in the form it is parsed from
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}>{}", self.trigger, self.command)
+    }
+}
+
+/// The bindings of MIDI messages to commands, with a command waiting to be bound by "MIDI learn"
+#[derive(Debug, Default, Getters)]
+pub struct Bindings {
+    #[getset(get = "pub")]
+    bindings: Vec<Binding>,
+    /// command to bind to the next controller message or program change
+    #[getset(get = "pub")]
+    learning: Option<String>,
+}
+
+impl Bindings {
+    /// Bind a command to a trigger from a definition like `<trigger>><command>` (e.g.
+    /// `cc20:127>mode=chord` or `pc3>undo`), replacing the command bound to the same trigger
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the trigger is not valid or no command is given.
+    pub fn bind(&mut self, definition: &str) -> Result<&Binding, BindingError> {
+        let (trigger, command) = definition
+            .split_once('>')
+            .filter(|(_, command)| !command.is_empty())
+            .ok_or_else(|| BindingError::InvalidBindingString(definition.into()))?;
+        let trigger: Trigger = trigger.parse()?;
+        Ok(self.insert(trigger, command.into()))
+    }
+
+    /// Remove the binding of the given trigger, returning whether there was one
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the trigger is not valid.
+    pub fn unbind(&mut self, trigger: &str) -> Result<bool, BindingError> {
+        let trigger: Trigger = trigger.parse()?;
+        let count = self.bindings.len();
+        self.bindings.retain(|binding| binding.trigger != trigger);
+        Ok(self.bindings.len() < count)
+    }
+
+    /// Remove all bindings
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Bind the given command to the next controller message or program change which arrives
+    pub fn learn(&mut self, command: &str) {
+        self.learning = Some(command.into());
+    }
+
+    /// If a command waits to be learned and the given message can trigger it, bind it to the
+    /// message
+    pub fn learn_from(&mut self, message: &MidiMessageType) -> Option<&Binding> {
+        let trigger = self.learning.as_ref().and(Trigger::learn(message))?;
+        let command = self.learning.take()?;
+        Some(self.insert(trigger, command))
+    }
+
+    /// The command bound to the given message
+    pub fn command(&self, message: &MidiMessageType) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.trigger.matches(message))
+            .map(|binding| binding.command.as_str())
+    }
+
+    /// Bind a command to a trigger, replacing the command bound to the same trigger
+    fn insert(&mut self, trigger: Trigger, command: String) -> &Binding {
+        self.bindings.retain(|binding| binding.trigger != trigger);
+        self.bindings.push(Binding { trigger, command });
+        self.bindings.last().expect("A binding was just added")
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingError {
+    /// The string is not a valid binding of a trigger to a command
+    InvalidBindingString(String),
+    /// The string is not a valid trigger
+    InvalidTriggerString(String),
+}
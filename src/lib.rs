@@ -1,5 +1,8 @@
+pub mod binding;
 pub mod lily;
 pub mod midi;
+pub mod output;
+pub mod zone;
 
 pub type MidiNote = u8;
 
@@ -18,12 +21,38 @@ make_lily_str_map!(
     PedalChord, "pedal-chord", "pc";
     /// Behave like [Mode::Single] when the pedal is pressed, otherwise behave like [Mode::Chord]
     PedalSingle, "pedal-single", "ps";
+    /// Separate overlapping notes into voices
+    ///
+    /// Notes held while others are played form independent voices, which are output as a
+    /// polyphonic `<< { } \\ { } >>` fragment once everything was released.
+    Voices, "voices", "v";
 );
 
 pub enum InputModeError {
     InvalidModeString(String),
 }
 
+make_lily_str_map!(
+    /// What should be output for the notes
+    #[derive(Default)]
+    OutputMode;
+    OutputModeError::InvalidOutputModeString;
+    /// Output LilyPond notes with pitches
+    #[default]
+    Notes, "notes", "n";
+    /// Output LilyPond `\drummode` names, taking the notes as General MIDI percussion
+    Drums, "drums", "d";
+    /// Output LilyPond `\chordmode` names of the chords, such as `c:m7` or `f:maj9/a`
+    ChordNames, "chord-names", "cn";
+    /// Output the lowest note of each chord as a LilyPond note, and the intervals above it as
+    /// `\figuremode` figures in a separate stream
+    FiguredBass, "figured-bass", "fb";
+);
+
+pub enum OutputModeError {
+    InvalidOutputModeString(String),
+}
+
 /// List all available options to stdout
 pub trait ListOptions {
     fn list_options();
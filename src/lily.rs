@@ -2,19 +2,45 @@
 pub mod macros;
 
 mod accidentals;
+mod chord;
+mod chord_name;
+mod clef;
+mod context;
+mod drums;
+mod figured_bass;
+mod fretboard;
+mod hands;
+mod instrument;
 mod keysignature;
+mod keyswitch;
 
 mod language;
+mod modifier;
 mod note;
 mod octave_entry;
 mod parameters;
+mod pitch_bend;
+mod voices;
 
 mod errors;
 
 pub use accidentals::*;
+pub use chord::*;
+pub use chord_name::*;
+pub use clef::*;
+pub use context::*;
+pub use drums::*;
 pub use errors::*;
+pub use figured_bass::*;
+pub use fretboard::*;
+pub use hands::*;
+pub use instrument::*;
 pub use keysignature::*;
+pub use keyswitch::*;
 pub use language::*;
+pub use modifier::*;
 pub use note::*;
 pub use octave_entry::*;
 pub use parameters::*;
+pub use pitch_bend::*;
+pub use voices::*;
@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::MidiNote;
+
+use super::{LilyNote, LilyParameters, Note, NATURAL_PITCH_CLASSES};
+
+/// A chord interpreted as thirds stacked on top of a root
+///
+/// Every chord member is described by its interval above the root (in semitones within an
+/// octave), which is mapped to the number of diatonic steps it lies above the root. A major
+/// third (4 semitones) is for example two steps above the root, whereas an augmented ninth (3
+/// semitones) is only one step above it.
+#[derive(Debug)]
+pub struct ChordAnalysis {
+    /// pitch class of the root (0-11)
+    root: MidiNote,
+    /// intervals above the root (0-11) mapped to diatonic steps above the root (0-6)
+    degrees: BTreeMap<u8, u8>,
+    /// pitch class of the lowest note (0-11)
+    bass: MidiNote,
+    /// how well the chord members fit the tertian interpretation
+    score: i8,
+}
+
+impl ChordAnalysis {
+    /// Identify the root of the given chord and the role of its members
+    ///
+    /// Returns `None` if the notes do not form a chord which can be built from thirds (or from
+    /// a suspended second or fourth), such as single notes, octaves or clusters.
+    pub fn new(notes: &BTreeSet<MidiNote>) -> Option<Self> {
+        let mut best: Option<(i8, ChordAnalysis)> = None;
+        for analysis in Self::candidates(notes) {
+            // prefer root position for otherwise equivalent interpretations
+            let score = analysis.score + i8::from(analysis.root == analysis.bass);
+            if best.as_ref().is_none_or(|(s, _)| score > *s) {
+                best = Some((score, analysis));
+            }
+        }
+        match best {
+            Some((_, analysis)) if analysis.is_tertian() => Some(analysis),
+            _ => None,
+        }
+    }
+
+    /// All interpretations of the given chord which can be built from thirds (or from a
+    /// suspended second or fourth), one for each possible root
+    ///
+    /// Returns an empty list for single notes, octaves or clusters.
+    pub fn interpretations(notes: &BTreeSet<MidiNote>) -> Vec<Self> {
+        Self::candidates(notes)
+            .into_iter()
+            .filter(Self::is_tertian)
+            .collect()
+    }
+
+    /// Interpret the chord with each of its pitch classes as the root
+    fn candidates(notes: &BTreeSet<MidiNote>) -> Vec<Self> {
+        let Some(bass) = notes.first().map(|n| n % 12) else {
+            return Vec::new();
+        };
+        let pitch_classes: BTreeSet<u8> = notes.iter().map(|n| n % 12).collect();
+        if pitch_classes.len() < 2 {
+            return Vec::new();
+        }
+        pitch_classes
+            .iter()
+            .map(|root| {
+                let intervals: BTreeSet<u8> = pitch_classes
+                    .iter()
+                    .map(|pc| (pc + 12 - root) % 12)
+                    .collect();
+                let degrees = Self::degrees_from_intervals(&intervals);
+                ChordAnalysis {
+                    root: *root,
+                    score: Self::rate(&degrees),
+                    degrees,
+                    bass,
+                }
+            })
+            .collect()
+    }
+
+    /// Pitch class of the root of the chord (0-11)
+    pub fn root(&self) -> MidiNote {
+        self.root
+    }
+
+    /// Intervals of the chord members above the root (0-11), mapped to diatonic steps
+    pub fn degrees(&self) -> &BTreeMap<u8, u8> {
+        &self.degrees
+    }
+
+    /// Pitch class of the lowest note of the chord (0-11)
+    pub fn bass(&self) -> MidiNote {
+        self.bass
+    }
+
+    /// How well the chord members fit the tertian interpretation, higher being better
+    pub fn score(&self) -> i8 {
+        self.score
+    }
+
+    /// Map the intervals above a root to the diatonic steps they represent
+    fn degrees_from_intervals(intervals: &BTreeSet<u8>) -> BTreeMap<u8, u8> {
+        let has = |i: u8| intervals.contains(&i);
+        intervals
+            .iter()
+            .map(|interval| {
+                let degree = match interval {
+                    0 => 0,
+                    // minor and major ninth
+                    1 | 2 => 1,
+                    // augmented ninth if there is a major third, otherwise minor third
+                    3 => match has(4) {
+                        true => 1,
+                        false => 2,
+                    },
+                    4 => 2,
+                    // fourth or eleventh
+                    5 => 3,
+                    // augmented eleventh if there is a perfect fifth, otherwise diminished fifth
+                    6 => match has(7) {
+                        true => 3,
+                        false => 4,
+                    },
+                    7 => 4,
+                    // minor thirteenth, unless it is an augmented fifth on a major third
+                    8 => match has(7) || has(3) || !has(4) {
+                        true => 5,
+                        false => 4,
+                    },
+                    // diminished seventh on a diminished triad, otherwise sixth or thirteenth
+                    9 => match has(3) && has(6) && !has(7) && !has(10) && !has(11) {
+                        true => 6,
+                        false => 5,
+                    },
+                    // minor and major seventh
+                    _ => 6,
+                };
+                (*interval, degree)
+            })
+            .collect()
+    }
+
+    /// Rate how well the chord members fit the tertian interpretation
+    fn rate(degrees: &BTreeMap<u8, u8>) -> i8 {
+        let has = |i: u8| degrees.contains_key(&i);
+        let has_third = degrees.iter().any(|(i, d)| *d == 2 && (*i == 3 || *i == 4));
+        let mut score = 0;
+        if has_third {
+            score += 4;
+        } else if has(7) && (has(2) || has(5)) {
+            // suspended chord
+            score += 2;
+        }
+        for (interval, degree) in degrees {
+            score += match (interval, degree) {
+                (0, _) => 0,
+                (3 | 4, 2) => 0,
+                (7, 4) => 3,
+                (6 | 8, 4) => 2,
+                (9..=11, 6) => 2,
+                (2, 1) | (5, 3) | (9, 5) => -1,
+                _ => -2,
+            };
+        }
+        score
+    }
+
+    /// Whether all chord members are regular chord tones, without an altered ninth, eleventh or
+    /// thirteenth (e.g. `c:6` is regular, whereas `a:m5.6-` is not)
+    pub fn is_regular(&self) -> bool {
+        self.degrees.iter().all(|degree| {
+            matches!(
+                degree,
+                (0, _) | (3 | 4, 2) | (7, 4) | (6 | 8, 4) | (9..=11, 6) | (2, 1) | (5, 3) | (9, 5)
+            )
+        })
+    }
+
+    /// Whether the chord is built from a third, or from a suspended second or fourth
+    fn is_tertian(&self) -> bool {
+        let has = |i: u8| self.degrees.contains_key(&i);
+        has(3) && self.degrees[&3] == 2 || has(4) || has(7) && (has(2) || has(5))
+    }
+
+    /// Spell every chord member consistently relative to the root
+    ///
+    /// The root is spelled such that the chord needs as few accidentals as possible with respect
+    /// to the key signature. In case of a tie, the spelling given by the key signature and the
+    /// accidentals for out-of-key notes is preferred.
+    ///
+    /// Returns a map from pitch classes (0-11) to their spelling, or `None` if no consistent
+    /// spelling exists.
+    pub fn spell(&self, parameters: &LilyParameters) -> Option<HashMap<MidiNote, Note>> {
+        let preferred = LilyNote::spell(self.root, parameters);
+        let mut candidates = vec![preferred];
+        for letter in 0..7 {
+            for alteration in -1..=1 {
+                if let Some(note) = Note::from_letter_alteration(letter, alteration) {
+                    if note.pitch_class() == self.root && note != preferred {
+                        candidates.push(note);
+                    }
+                }
+            }
+        }
+        let mut best: Option<(u8, HashMap<MidiNote, Note>)> = None;
+        for root in candidates {
+            if let Some(spelling) = self.spell_from_root(&root) {
+                let cost = spelling
+                    .values()
+                    .map(|note| Self::accidental_cost(note, parameters))
+                    .sum();
+                if best.as_ref().is_none_or(|(c, _)| cost < *c) {
+                    best = Some((cost, spelling));
+                }
+            }
+        }
+        best.map(|(_, spelling)| spelling)
+    }
+
+    /// Spell every chord member by stacking its diatonic steps on the given root
+    fn spell_from_root(&self, root: &Note) -> Option<HashMap<MidiNote, Note>> {
+        let mut spelling = HashMap::new();
+        for (interval, degree) in &self.degrees {
+            let letter = (root.letter() + degree) % 7;
+            let pitch_class = (self.root + interval) % 12;
+            let natural = NATURAL_PITCH_CLASSES[letter as usize] as i8;
+            // shortest distance from the natural note to the pitch class
+            let alteration = (pitch_class as i8 - natural + 6).rem_euclid(12) - 6;
+            spelling.insert(
+                pitch_class,
+                Note::from_letter_alteration(letter, alteration)?,
+            );
+        }
+        Some(spelling)
+    }
+
+    /// Number of accidentals needed to write the note in the current key signature
+    ///
+    /// Double sharps and double flats are penalized further, as they are harder to read.
+    fn accidental_cost(note: &Note, parameters: &LilyParameters) -> u8 {
+        let alteration = note.alteration();
+        let cost = (alteration - parameters.written_key().alteration(note.letter())).unsigned_abs();
+        match alteration.abs() {
+            2 => cost + 1,
+            _ => cost,
+        }
+    }
+}
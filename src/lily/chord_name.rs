@@ -0,0 +1,249 @@
+use std::collections::BTreeSet;
+
+use crate::MidiNote;
+
+use super::{ChordAnalysis, ChordNameError, LilyNote, LilyParameters, Note};
+
+make_lily_str_map!(
+    /// Criteria for choosing between several interpretations of a chord, such as `<c e g a>`
+    /// which is both a `c:6` and an `a:m7/c`
+    ChordNamePreference;
+    ChordNameError::InvalidChordNamePreferenceString;
+    /// Prefer the interpretation which is built from thirds most regularly
+    Thirds, "thirds", "t";
+    /// Prefer the interpretation with the lowest note as its root, avoiding inversions
+    Bass, "bass", "b";
+    /// Prefer the interpretation with the shortest name
+    Simple, "simple", "s";
+);
+
+/// Name the given notes in LilyPond `\chordmode` syntax, such as `c:m7`, `f:maj9/a` or
+/// `g:7.9-`, with the root and bass named in the current language
+///
+/// A single note is named as the root alone (`c:1`), and a perfect fifth as a power chord
+/// (`c:1.5`). If the chord can be interpreted with several roots, the interpretations are
+/// compared by the given preferences in order.
+///
+/// Returns `None` if the notes do not form a chord which can be built from thirds (or from a
+/// suspended second or fourth).
+pub fn chord_name(
+    notes: &BTreeSet<MidiNote>,
+    preferences: &[ChordNamePreference],
+    parameters: &LilyParameters,
+) -> Option<String> {
+    let name = |note: &Note| parameters.language().note_to_str(note);
+    let pitch_classes: BTreeSet<MidiNote> = notes.iter().map(|note| note % 12).collect();
+    let bass = *notes.first()?;
+    match pitch_classes.len() {
+        1 => return Some(format!("{}:1", name(&LilyNote::spell(bass, parameters)))),
+        2 => {
+            let fifth = (bass + 7) % 12;
+            if pitch_classes.contains(&fifth) {
+                return Some(format!("{}:1.5", name(&LilyNote::spell(bass, parameters))));
+            }
+        }
+        _ => (),
+    }
+    let mut interpretations: Vec<(ChordAnalysis, String)> = ChordAnalysis::interpretations(notes)
+        .into_iter()
+        .map(|analysis| {
+            let modifiers = analysis.chordmode_modifiers();
+            (analysis, modifiers)
+        })
+        .collect();
+    interpretations.sort_by(|(a, a_modifiers), (b, b_modifiers)| {
+        preferences
+            .iter()
+            .map(|preference| match preference {
+                ChordNamePreference::Thirds => b.score().cmp(&a.score()),
+                // root position only wins if it does not need altered chord tones, such that
+                // inversions of plain triads keep their name (`f/a` rather than `a:m5.6-`)
+                ChordNamePreference::Bass => {
+                    let root_position = |c: &ChordAnalysis| c.root() == c.bass() && c.is_regular();
+                    root_position(b).cmp(&root_position(a))
+                }
+                ChordNamePreference::Simple => a_modifiers.len().cmp(&b_modifiers.len()),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let (analysis, modifiers) = interpretations.into_iter().next()?;
+    let spelling = analysis.spell(parameters).unwrap_or_default();
+    let spell = |pitch_class: MidiNote| match spelling.get(&pitch_class) {
+        Some(note) => *note,
+        None => LilyNote::spell(pitch_class, parameters),
+    };
+    let mut chord = String::from(name(&spell(analysis.root())));
+    if !modifiers.is_empty() {
+        chord.push(':');
+        chord.push_str(&modifiers);
+    }
+    if analysis.bass() != analysis.root() {
+        chord.push('/');
+        chord.push_str(name(&spell(analysis.bass())));
+    }
+    Some(chord)
+}
+
+impl ChordAnalysis {
+    /// The chord modifiers following the root in `\chordmode`, such as `m7`, `maj9` or `7.9-`
+    ///
+    /// The modifiers are empty for a major triad.
+    pub fn chordmode_modifiers(&self) -> String {
+        // find which of the given intervals is present as the given diatonic step
+        let find = |intervals: &[u8], degree: u8| {
+            intervals
+                .iter()
+                .find(|interval| self.degrees().get(interval) == Some(&degree))
+                .copied()
+        };
+        let third = find(&[4, 3], 2);
+        let fifth = find(&[7, 6, 8], 4);
+        let seventh = find(&[10, 11, 9], 6);
+        let mut ninth = find(&[2, 1, 3], 1);
+        let mut fourth = find(&[5, 6], 3);
+        let sixth = find(&[9, 8], 5);
+
+        match (third, fifth, seventh) {
+            (Some(3), Some(6), None) if ninth.is_none() && fourth.is_none() && sixth.is_none() => {
+                return "dim".into()
+            }
+            (Some(3), Some(6), Some(9)) if ninth.is_none() && fourth.is_none() => {
+                return "dim7".into()
+            }
+            (Some(4), Some(8), None) if ninth.is_none() && fourth.is_none() && sixth.is_none() => {
+                return "aug".into()
+            }
+            (Some(4), Some(8), Some(10)) if ninth.is_none() && fourth.is_none() => {
+                return "aug7".into()
+            }
+            _ => (),
+        }
+
+        // a fourth without a third is a suspension rather than an eleventh, as is a lone second
+        let suspension = match third {
+            Some(_) => "",
+            None => match (fourth.take(), ninth) {
+                (Some(_), _) => "sus4",
+                (None, Some(2)) => {
+                    ninth = None;
+                    "sus2"
+                }
+                _ => "",
+            },
+        };
+
+        let mut number: Option<u8> = seventh.map(|_| 7);
+        let mut steps: Vec<(u8, &str)> = Vec::new();
+        match ninth {
+            Some(2) if number == Some(7) => number = Some(9),
+            Some(2) => steps.push((9, "9")),
+            Some(1) => steps.push((9, "9-")),
+            Some(3) => steps.push((9, "9+")),
+            _ => (),
+        }
+        match fourth {
+            Some(5) if number == Some(9) && sixth.is_none() => number = Some(11),
+            Some(5) => steps.push((11, "11")),
+            Some(6) => steps.push((11, "11+")),
+            _ => (),
+        }
+        match (sixth, seventh) {
+            (Some(9), Some(_)) if number == Some(9) => number = Some(13),
+            (Some(9), Some(_)) => steps.push((13, "13")),
+            (Some(8), Some(_)) => steps.push((13, "13-")),
+            (Some(9), None) => number = Some(6),
+            (Some(8), None) => steps.push((6, "6-")),
+            _ => (),
+        }
+        let mut number = number.map(|number| number.to_string()).unwrap_or_default();
+        let fifth_step = match fifth {
+            Some(6) => Some("5-"),
+            Some(8) => Some("5+"),
+            _ => None,
+        };
+        if let Some(step) = fifth_step {
+            match number.is_empty() {
+                true => number = step.into(),
+                false => steps.push((5, step)),
+            }
+        }
+        let quality = match (third, seventh) {
+            (Some(3), Some(11)) => {
+                steps.push((7, "7+"));
+                "m"
+            }
+            (Some(3), _) => "m",
+            (_, Some(11)) => "maj",
+            _ => "",
+        };
+        if number.is_empty() && !steps.is_empty() && suspension.is_empty() {
+            number = "5".into();
+        }
+        steps.sort();
+        let mut modifiers = format!("{quality}{number}{suspension}");
+        for (_, step) in steps {
+            if !modifiers.is_empty() {
+                modifiers.push('.');
+            }
+            modifiers.push_str(step);
+        }
+        // a lone major seventh on a minor chord is written as `m7+`
+        modifiers.replace("m7.7+", "m7+")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(notes: &[MidiNote], parameters: &LilyParameters) -> Option<String> {
+        parameters.chord_name(&notes.iter().copied().collect())
+    }
+
+    #[test]
+    fn names_seventh_and_ninth_chords() {
+        let parameters = LilyParameters::for_tests();
+        assert_eq!(
+            name(&[60, 63, 67, 70], &parameters).as_deref(),
+            Some("c:m7")
+        );
+        assert_eq!(
+            name(&[57, 60, 64, 65, 67], &parameters).as_deref(),
+            Some("f:maj9/a")
+        );
+        assert_eq!(
+            name(&[55, 59, 62, 65, 68], &parameters).as_deref(),
+            Some("g:7.9-")
+        );
+    }
+
+    #[test]
+    fn names_single_notes_and_power_chords() {
+        let parameters = LilyParameters::for_tests();
+        assert_eq!(name(&[60], &parameters).as_deref(), Some("c:1"));
+        assert_eq!(name(&[60, 67], &parameters).as_deref(), Some("c:1.5"));
+    }
+
+    #[test]
+    fn names_added_sixths_in_root_position() {
+        let parameters = LilyParameters::for_tests();
+        assert_eq!(name(&[60, 64, 67, 69], &parameters).as_deref(), Some("c:6"));
+        assert_eq!(
+            name(&[57, 60, 64, 67], &parameters).as_deref(),
+            Some("a:m7")
+        );
+    }
+
+    #[test]
+    fn prefers_interpretations_in_order() {
+        let mut parameters = LilyParameters::for_tests();
+        parameters
+            .set_chord_name_preferences_str("thirds,bass,simple")
+            .unwrap();
+        assert_eq!(
+            name(&[60, 64, 67, 69], &parameters).as_deref(),
+            Some("a:m7/c")
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use super::ClefError;
+
+make_lily_str_map!(
+    /// The clef of the staff the notes are written on
+    #[derive(Default)]
+    Clef;
+    ClefError::InvalidClefString;
+    #[default]
+    Treble, "treble", "G";
+    Bass, "bass", "F";
+    Alto, "alto", "C";
+    Tenor, "tenor";
+);
+
+impl Clef {
+    /// Diatonic step of the bottom line of the staff, counted from the C four octaves below
+    /// middle C (i.e. middle C is step 28)
+    fn bottom_line(&self) -> i16 {
+        match self {
+            Clef::Treble => 30, // E4
+            Clef::Bass => 18,   // G2
+            Clef::Alto => 24,   // F3
+            Clef::Tenor => 22,  // D3
+        }
+    }
+
+    /// Number of ledger lines needed for a note at the given diatonic step (see
+    /// [LilyNote::staff_step](super::LilyNote::staff_step)), positive above the staff and
+    /// negative below it
+    pub fn ledger_lines(&self, step: i16) -> i16 {
+        let bottom = self.bottom_line();
+        let top = bottom + 8;
+        if step > top {
+            (step - top) / 2
+        } else if step < bottom {
+            -((bottom - step) / 2)
+        } else {
+            0
+        }
+    }
+}
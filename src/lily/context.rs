@@ -0,0 +1,628 @@
+use std::collections::BTreeSet;
+
+use getset::Getters;
+use regex::Regex;
+
+use crate::MidiNote;
+
+use super::{
+    Language, LilyKeySignature, LilypondContextError, Note, OctaveEntry, NATURAL_PITCH_CLASSES,
+};
+
+/// State of LilyPond's parser at the end of a piece of LilyPond source, such as the text before
+/// the cursor in an editor
+///
+/// Only what is needed to generate the next notes is tracked: the octave entry mode with its
+/// reference pitch, the last chord (for `q`), the key signature and the note name language.
+/// Everything else in the source (durations, articulations, markup, lyrics, Scheme, ...) is
+/// skipped.
+#[derive(Debug, Getters)]
+pub struct LilyContext {
+    /// the note name language at the end of the source
+    #[getset(get = "pub")]
+    pub(super) language: Language,
+    /// the last key signature, unless the source has none
+    #[getset(get = "pub")]
+    pub(super) key: Option<LilyKeySignature>,
+    /// the octave entry mode of the innermost block around the end of the source
+    #[getset(get = "pub")]
+    pub(super) octave_entry: OctaveEntry,
+    /// the reference pitch of the innermost `\fixed` block, without its accidental
+    #[getset(get = "pub")]
+    pub(super) fixed_reference: Option<MidiNote>,
+    /// the pitch (and its spelling) following notes are relative to in a `\relative` block
+    #[getset(get = "pub")]
+    pub(super) previous_absolute_note_reference: Option<(MidiNote, Note)>,
+    /// the last chord, which is repeated by `q`
+    #[getset(get = "pub")]
+    pub(super) previous_chord: Option<BTreeSet<MidiNote>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    /// A backslash command, without the backslash
+    Command(&'a str),
+    /// Any other run of characters, such as notes, durations or identifiers
+    Word(&'a str),
+    /// The contents of a string
+    String(&'a str),
+    /// A Scheme expression, whose contents are irrelevant
+    Scheme,
+    OpenBrace,
+    CloseBrace,
+    OpenSimultaneous,
+    CloseSimultaneous,
+    OpenChord,
+    CloseChord,
+}
+
+/// Octave entry mode, which is scoped to the music expression following `\relative`, `\fixed`
+/// or `\absolute`
+#[derive(Debug, Clone)]
+struct OctaveState {
+    octave_entry: OctaveEntry,
+    /// only relevant for [OctaveEntry::Fixed]
+    fixed_reference: MidiNote,
+    /// only relevant for [OctaveEntry::Relative]
+    reference: Option<(MidiNote, Note)>,
+}
+
+#[derive(Debug)]
+struct ChordState {
+    notes: BTreeSet<MidiNote>,
+    /// the first note of the chord becomes the reference after the chord
+    first: Option<(MidiNote, Note)>,
+    /// octave state to restore after a chord which is the only music of a `\relative`
+    restore: Option<OctaveState>,
+}
+
+/// A note parsed from the source, before its octave was resolved
+struct ParsedNote {
+    spelling: Note,
+    /// number of octave marks (`'` positive, `,` negative)
+    octave: i8,
+    /// absolute octave of an octave check (`c'='`)
+    octave_check: Option<i8>,
+}
+
+/// Commands whose next word is not music
+const SKIP_WORD: [&str; 10] = [
+    "clef", "new", "context", "change", "set", "unset", "override", "revert", "tweak", "time",
+];
+
+/// Commands whose next argument (a block, a string or a word) is not music
+const SKIP_ARGUMENT: [&str; 16] = [
+    "markup",
+    "markuplist",
+    "header",
+    "paper",
+    "layout",
+    "midi",
+    "with",
+    "lyricmode",
+    "lyrics",
+    "addlyrics",
+    "chordmode",
+    "chords",
+    "drummode",
+    "drums",
+    "figuremode",
+    "figures",
+];
+
+impl LilyContext {
+    /// Determine the state of LilyPond's parser at the end of the given source
+    ///
+    /// The note names are read in the given language until a `\language` (or an `\include` of
+    /// a language file) switches to another one. Nested `\relative`, `\fixed` and `\absolute`
+    /// blocks are scoped like in LilyPond, such that the octave entry mode and reference pitch
+    /// are those of the innermost block which is still open at the end of the source. Within a
+    /// `\relative` block, chords, `q`, octave checks (`c'=''`) and `\octaveCheck` update the
+    /// reference like LilyPond does.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a note falls outside of the MIDI range, if the key
+    /// signature cannot be represented, or if an unknown language is selected.
+    pub fn from_lilypond_str(
+        text: &str,
+        language: &Language,
+    ) -> Result<Self, LilypondContextError> {
+        Parser::new(text, language.clone()).parse()
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    language: Language,
+    key: Option<LilyKeySignature>,
+    state: OctaveState,
+    /// octave state to apply to the next music expression
+    pending: Option<OctaveState>,
+    /// octave states to restore when the corresponding block is closed
+    scopes: Vec<Option<OctaveState>>,
+    chord: Option<ChordState>,
+    previous_chord: Option<BTreeSet<MidiNote>>,
+    re_note: Regex,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, language: Language) -> Self {
+        Parser {
+            tokens: tokenize(text),
+            position: 0,
+            language,
+            key: None,
+            state: OctaveState {
+                octave_entry: OctaveEntry::Absolute,
+                fixed_reference: 60,
+                reference: None,
+            },
+            pending: None,
+            scopes: Vec::new(),
+            chord: None,
+            previous_chord: None,
+            re_note: Regex::new(
+                r"^(?<note>\p{Alphabetic}+)(?<octave>'+|,+)?[!?]?(?:=(?<check>'+|,*))?(?:[^\p{Alphabetic}].*)?$",
+            )
+            .expect("Regex is valid"),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn parse(mut self) -> Result<LilyContext, LilypondContextError> {
+        while let Some(token) = self.next() {
+            match token {
+                Token::Command(command) => self.command(command)?,
+                Token::Word(word) => match self.peek() {
+                    // assignment to a variable
+                    Some(Token::Word("=")) => self.position += 1,
+                    _ => self.word(word)?,
+                },
+                Token::String(_) | Token::Scheme => (),
+                Token::OpenBrace | Token::OpenSimultaneous => {
+                    let restore = self.pending.take().map(|pending| self.enter(pending));
+                    self.scopes.push(restore);
+                }
+                Token::CloseBrace | Token::CloseSimultaneous => {
+                    if let Some(Some(restore)) = self.scopes.pop() {
+                        self.state = restore;
+                    }
+                }
+                Token::OpenChord => {
+                    let restore = self.pending.take().map(|pending| self.enter(pending));
+                    self.chord = Some(ChordState {
+                        notes: BTreeSet::new(),
+                        first: None,
+                        restore,
+                    });
+                }
+                Token::CloseChord => {
+                    if let Some(chord) = self.chord.take() {
+                        if let OctaveEntry::Relative = self.state.octave_entry {
+                            self.state.reference = chord.first.or(self.state.reference);
+                        }
+                        if !chord.notes.is_empty() {
+                            self.previous_chord = Some(chord.notes);
+                        }
+                        if let Some(restore) = chord.restore {
+                            self.state = restore;
+                        }
+                    }
+                }
+            }
+        }
+        let OctaveState {
+            octave_entry,
+            fixed_reference,
+            reference,
+        } = self.state;
+        Ok(LilyContext {
+            language: self.language,
+            key: self.key,
+            fixed_reference: match octave_entry {
+                OctaveEntry::Fixed => Some(fixed_reference),
+                _ => None,
+            },
+            previous_absolute_note_reference: match octave_entry {
+                OctaveEntry::Relative => reference,
+                _ => None,
+            },
+            octave_entry,
+            previous_chord: self.previous_chord,
+        })
+    }
+
+    /// Switch to the given octave state, returning the one to restore afterwards
+    fn enter(&mut self, state: OctaveState) -> OctaveState {
+        std::mem::replace(&mut self.state, state)
+    }
+
+    fn command(&mut self, command: &str) -> Result<(), LilypondContextError> {
+        match command {
+            "language" => {
+                if let Some(Token::String(language)) = self.peek() {
+                    self.position += 1;
+                    self.language = language.try_into().map_err(|_| {
+                        LilypondContextError::InvalidLanguageString(language.into())
+                    })?;
+                }
+            }
+            "include" => {
+                if let Some(Token::String(file)) = self.peek() {
+                    self.position += 1;
+                    // the old way of selecting a language, e.g. `\include "deutsch.ly"`
+                    if let Some(Ok(language)) = file.strip_suffix(".ly").map(Language::try_from) {
+                        self.language = language;
+                    }
+                }
+            }
+            "key" => self.key()?,
+            "relative" => {
+                let reference = match self.peek_pitch() {
+                    Some(note) => {
+                        self.position += 1;
+                        Some(self.absolute(&note)?)
+                    }
+                    // the first note is then effectively entered in absolute octave entry
+                    None => Some((53, Note::F)),
+                };
+                self.pending = Some(OctaveState {
+                    octave_entry: OctaveEntry::Relative,
+                    fixed_reference: self.state.fixed_reference,
+                    reference,
+                });
+            }
+            "fixed" => {
+                if let Some(note) = self.peek_pitch() {
+                    self.position += 1;
+                    let (note, spelling) = self.absolute(&note)?;
+                    self.pending = Some(OctaveState {
+                        octave_entry: OctaveEntry::Fixed,
+                        fixed_reference: (note as i16 - spelling.alteration() as i16) as MidiNote,
+                        reference: None,
+                    });
+                }
+            }
+            "absolute" => {
+                self.pending = Some(OctaveState {
+                    octave_entry: OctaveEntry::Absolute,
+                    fixed_reference: self.state.fixed_reference,
+                    reference: None,
+                });
+            }
+            "octaveCheck" => {
+                if let Some(note) = self.peek_pitch() {
+                    self.position += 1;
+                    let reference = self.absolute(&note)?;
+                    if let OctaveEntry::Relative = self.state.octave_entry {
+                        self.state.reference = Some(reference);
+                    }
+                }
+            }
+            "transpose" => self.skip_words(2),
+            "transposition" => self.skip_words(1),
+            "lyricsto" => {
+                self.skip_argument();
+                self.skip_argument();
+            }
+            _ if SKIP_WORD.contains(&command) => self.skip_words(1),
+            _ if SKIP_ARGUMENT.contains(&command) => self.skip_argument(),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Parse the key signature following `\key`
+    fn key(&mut self) -> Result<(), LilypondContextError> {
+        let Some(Token::Word(tonic)) = self.peek() else {
+            return Ok(());
+        };
+        let Some(note) = self.parse_note(tonic) else {
+            return Ok(());
+        };
+        self.position += 1;
+        let Some(Token::Command(mode)) = self.peek() else {
+            return Ok(());
+        };
+        self.position += 1;
+        let tonic_fifths = note.spelling.fifths();
+        let invalid = || LilypondContextError::InvalidKey(format!("{tonic} \\{mode}"));
+        let (offset, minor) = match mode {
+            "major" | "ionian" => (0, false),
+            "minor" | "aeolian" => (-3, true),
+            "dorian" => (-2, false),
+            "phrygian" => (-4, false),
+            "lydian" => (1, false),
+            "mixolydian" => (-1, false),
+            "locrian" => (-5, false),
+            _ => return Err(invalid()),
+        };
+        self.key =
+            Some(LilyKeySignature::from_fifths(tonic_fifths + offset, minor).ok_or_else(invalid)?);
+        Ok(())
+    }
+
+    fn word(&mut self, word: &str) -> Result<(), LilypondContextError> {
+        // chord repetitions, rests and skips do not change the reference pitch
+        if matches!(word.get(..1), Some("q" | "r" | "R" | "s"))
+            && !word[1..].starts_with(|c: char| c.is_alphabetic())
+        {
+            return Ok(());
+        }
+        let Some(note) = self.parse_note(word) else {
+            return Ok(());
+        };
+        // a single note as the music of a `\relative`
+        let restore = self.pending.take().map(|pending| self.enter(pending));
+        let (value, spelling) = match self.state.octave_entry {
+            OctaveEntry::Absolute => self.absolute(&note)?,
+            OctaveEntry::Fixed => {
+                let octave = (self.state.fixed_reference / 12) as i8 - 4 + note.octave;
+                self.resolve(word, &note, note.octave_check.unwrap_or(octave))?
+            }
+            OctaveEntry::Relative => match self.state.reference {
+                Some(reference) => {
+                    let octave = relative_octave(reference, &note.spelling) + note.octave;
+                    self.resolve(word, &note, note.octave_check.unwrap_or(octave))?
+                }
+                None => self.absolute(&note)?,
+            },
+        };
+        if let OctaveEntry::Relative = self.state.octave_entry {
+            self.state.reference = Some((value, spelling));
+        }
+        if let Some(chord) = self.chord.as_mut() {
+            chord.notes.insert(value);
+            chord.first.get_or_insert((value, spelling));
+        }
+        if let Some(restore) = restore {
+            self.state = restore;
+        }
+        Ok(())
+    }
+
+    /// Parse the note at the start of the word, if it is one in the current language
+    fn parse_note(&self, word: &str) -> Option<ParsedNote> {
+        let caps = self.re_note.captures(word)?;
+        let count_octave = |marks: &str| match marks.chars().next() {
+            Some(',') => -(marks.len() as i8),
+            Some(_) => marks.len() as i8,
+            None => 0,
+        };
+        Some(ParsedNote {
+            spelling: self.language.str_to_pitch(&caps["note"])?,
+            octave: caps.name("octave").map_or(0, |o| count_octave(o.as_str())),
+            octave_check: caps.name("check").map(|c| count_octave(c.as_str())),
+        })
+    }
+
+    /// The next token, if it is a note
+    fn peek_pitch(&self) -> Option<ParsedNote> {
+        match self.peek() {
+            Some(Token::Word(word)) => self.parse_note(word),
+            _ => None,
+        }
+    }
+
+    /// Get the note in absolute octave entry
+    fn absolute(&self, note: &ParsedNote) -> Result<(MidiNote, Note), LilypondContextError> {
+        let word = self.language.note_to_str(&note.spelling);
+        self.resolve(word, note, note.octave_check.unwrap_or(note.octave))
+    }
+
+    /// Get the note in the given absolute octave
+    fn resolve(
+        &self,
+        word: &str,
+        note: &ParsedNote,
+        octave: i8,
+    ) -> Result<(MidiNote, Note), LilypondContextError> {
+        let value = (octave as i16 + 4) * 12
+            + NATURAL_PITCH_CLASSES[note.spelling.letter() as usize] as i16
+            + note.spelling.alteration() as i16;
+        match value {
+            0..=127 => Ok((value as MidiNote, note.spelling)),
+            _ => Err(LilypondContextError::NoteOutOfRange(word.into())),
+        }
+    }
+
+    fn skip_words(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(Token::Word(_)) = self.peek() {
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Skip a block, a string or a word, along with any commands in front of it (such as in
+    /// `\markup \bold { ... }`)
+    fn skip_argument(&mut self) {
+        while let Some(token) = self.next() {
+            match token {
+                Token::Command(_) | Token::Scheme => (),
+                Token::OpenBrace => {
+                    let mut depth = 1;
+                    while depth > 0 {
+                        match self.next() {
+                            Some(Token::OpenBrace) => depth += 1,
+                            Some(Token::CloseBrace) => depth -= 1,
+                            Some(_) => (),
+                            None => return,
+                        }
+                    }
+                    return;
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Absolute octave of a note without octave marks in relative octave entry
+///
+/// The note is placed within a fourth of the reference, counting note letters, such that
+/// accidentals are irrelevant (i.e. `bis` after `f` goes up, `ces` after `g` goes down).
+fn relative_octave(reference: (MidiNote, Note), spelling: &Note) -> i8 {
+    let (value, reference_spelling) = reference;
+    let natural = value as i16 - reference_spelling.alteration() as i16;
+    let reference_step = natural.div_euclid(12) * 7 + reference_spelling.letter() as i16;
+    let step = reference_step
+        + (spelling.letter() as i16 - reference_spelling.letter() as i16 + 3).rem_euclid(7)
+        - 3;
+    (step.div_euclid(7) - 4) as i8
+}
+
+/// Split LilyPond source into tokens, dropping comments
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let at = |i: usize| chars.get(i).map(|(_, c)| *c);
+    let offset = |i: usize| chars.get(i).map_or(text.len(), |(o, _)| *o);
+    let is_word_char = |c: char| {
+        !c.is_whitespace() && !matches!(c, '{' | '}' | '<' | '>' | '"' | '%' | '#' | '$' | '\\')
+    };
+    let mut tokens = Vec::new();
+    let mut in_chord = false;
+    let mut i = 0;
+    while let Some(c) = at(i) {
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '%' => match at(i + 1) {
+                Some('{') => {
+                    i += 2;
+                    while at(i).is_some() && !(at(i) == Some('%') && at(i + 1) == Some('}')) {
+                        i += 1;
+                    }
+                    i += 2;
+                }
+                _ => {
+                    while at(i).is_some_and(|c| c != '\n') {
+                        i += 1;
+                    }
+                }
+            },
+            '"' => {
+                let start = i + 1;
+                i += 1;
+                while let Some(c) = at(i) {
+                    match c {
+                        '\\' => i += 2,
+                        '"' => break,
+                        _ => i += 1,
+                    }
+                }
+                tokens.push(Token::String(
+                    &text[offset(start)..offset(i.min(chars.len()))],
+                ));
+                i += 1;
+            }
+            '#' | '$' => {
+                i += 1;
+                while matches!(at(i), Some('#' | '\'' | '`' | ',')) {
+                    i += 1;
+                }
+                match at(i) {
+                    Some('(') => {
+                        let mut depth = 0;
+                        while let Some(c) = at(i) {
+                            match c {
+                                '(' => depth += 1,
+                                ')' => depth -= 1,
+                                '"' => {
+                                    i += 1;
+                                    while at(i).is_some_and(|c| c != '"') {
+                                        i += if at(i) == Some('\\') { 2 } else { 1 };
+                                    }
+                                }
+                                _ => (),
+                            }
+                            i += 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                    }
+                    Some('"') => {
+                        i += 1;
+                        while at(i).is_some_and(|c| c != '"') {
+                            i += if at(i) == Some('\\') { 2 } else { 1 };
+                        }
+                        i += 1;
+                    }
+                    _ => {
+                        while at(i).is_some_and(|c| is_word_char(c) && c != ')') {
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(Token::Scheme);
+            }
+            '\\' => {
+                let start = i + 1;
+                i += 1;
+                while at(i).is_some_and(|c| {
+                    c.is_alphabetic()
+                        || matches!(c, '-' | '_') && at(i + 1).is_some_and(char::is_alphabetic)
+                }) {
+                    i += 1;
+                }
+                if i == start {
+                    // single character commands, such as `\\`, `\<` or `\!`
+                    i += 1;
+                }
+                tokens.push(Token::Command(&text[offset(start)..offset(i)]));
+            }
+            '{' => {
+                tokens.push(Token::OpenBrace);
+                i += 1;
+            }
+            '}' => {
+                tokens.push(Token::CloseBrace);
+                i += 1;
+            }
+            '<' if !in_chord && at(i + 1) == Some('<') => {
+                tokens.push(Token::OpenSimultaneous);
+                i += 2;
+            }
+            '>' if !in_chord && at(i + 1) == Some('>') => {
+                tokens.push(Token::CloseSimultaneous);
+                i += 2;
+            }
+            '<' => {
+                in_chord = true;
+                tokens.push(Token::OpenChord);
+                i += 1;
+            }
+            '>' => {
+                in_chord = false;
+                tokens.push(Token::CloseChord);
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while let Some(c) = at(i) {
+                    if matches!(c, '-' | '^' | '_') && at(i + 1) == Some('>') {
+                        // accent articulation, such as in `c4->`
+                        i += 2;
+                    } else if is_word_char(c) {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Word(&text[offset(start)..offset(i)]));
+            }
+        }
+    }
+    tokens
+}
@@ -0,0 +1,65 @@
+use crate::MidiNote;
+
+/// The lowest note of the General MIDI percussion key map
+const GM_PERCUSSION_LOWEST: MidiNote = 35;
+
+/// LilyPond `\drummode` names for the General MIDI percussion key map, starting at
+/// [GM_PERCUSSION_LOWEST]
+const GM_PERCUSSION: [&str; 47] = [
+    "bda",   // 35 acoustic bass drum
+    "bd",    // 36 bass drum
+    "ss",    // 37 side stick
+    "sn",    // 38 acoustic snare
+    "hc",    // 39 hand clap
+    "sne",   // 40 electric snare
+    "tomfl", // 41 low floor tom
+    "hhc",   // 42 closed hi-hat
+    "tomfh", // 43 high floor tom
+    "hhp",   // 44 pedal hi-hat
+    "toml",  // 45 low tom
+    "hho",   // 46 open hi-hat
+    "tomml", // 47 low mid tom
+    "tommh", // 48 high mid tom
+    "cymc",  // 49 crash cymbal
+    "tomh",  // 50 high tom
+    "cymr",  // 51 ride cymbal
+    "cymch", // 52 chinese cymbal
+    "rb",    // 53 ride bell
+    "tamb",  // 54 tambourine
+    "cyms",  // 55 splash cymbal
+    "cb",    // 56 cowbell
+    "cymcb", // 57 crash cymbal 2
+    "vibs",  // 58 vibraslap
+    "cymrb", // 59 ride cymbal 2
+    "boh",   // 60 high bongo
+    "bol",   // 61 low bongo
+    "cghm",  // 62 mute high conga
+    "cgho",  // 63 open high conga
+    "cgl",   // 64 low conga
+    "timh",  // 65 high timbale
+    "timl",  // 66 low timbale
+    "agh",   // 67 high agogo
+    "agl",   // 68 low agogo
+    "cab",   // 69 cabasa
+    "mar",   // 70 maracas
+    "whs",   // 71 short whistle
+    "whl",   // 72 long whistle
+    "guis",  // 73 short guiro
+    "guil",  // 74 long guiro
+    "cl",    // 75 claves
+    "wbh",   // 76 high wood block
+    "wbl",   // 77 low wood block
+    "cuim",  // 78 mute cuica
+    "cuio",  // 79 open cuica
+    "trim",  // 80 mute triangle
+    "trio",  // 81 open triangle
+];
+
+/// The LilyPond `\drummode` name of the given note in the General MIDI percussion key map
+///
+/// Returns `None` if the note is not part of the key map.
+pub fn gm_drum_name(note: MidiNote) -> Option<&'static str> {
+    note.checked_sub(GM_PERCUSSION_LOWEST)
+        .and_then(|index| GM_PERCUSSION.get(index as usize))
+        .copied()
+}
@@ -7,6 +7,12 @@ pub enum LilypondNoteError {
     InvalidKeyString(String),
     /// The string was not recognized as a LilyPond note
     InvalidNoteString(String),
+    /// The language has no names for quarter tones
+    UnsupportedQuarterTone(super::Language),
+    /// The note is not a spelling of the given pitch class or MIDI note
+    InvalidSpelling(crate::MidiNote, super::Note),
+    /// The pitch falls outside of the MIDI range (0 to 127), given in quarter tones
+    OutsideMidiRange(i16),
 }
 
 #[derive(Debug)]
@@ -26,3 +32,67 @@ pub enum OctaveEntryError {
     /// The string was not recognized for octave entry mode
     InvalidOctaveEntryString(String),
 }
+
+#[derive(Debug)]
+pub enum OctaveCheckStyleError {
+    /// The string was not recognized for octave check styles
+    InvalidOctaveCheckStyleString(String),
+}
+
+#[derive(Debug)]
+pub enum PitchBendError {
+    /// The string was not recognized for pitch bend resolutions
+    InvalidPitchBendResolutionString(String),
+}
+
+#[derive(Debug)]
+pub enum LilypondContextError {
+    /// A note in the LilyPond source falls outside of the MIDI range
+    NoteOutOfRange(String),
+    /// The key signature has an unsupported mode, or more than 7 sharps or flats
+    InvalidKey(String),
+    /// The string was not recognized for languages
+    InvalidLanguageString(String),
+}
+
+#[derive(Debug)]
+pub enum ClefError {
+    /// The string was not recognized for clefs
+    InvalidClefString(String),
+}
+
+#[derive(Debug)]
+pub enum InstrumentError {
+    /// The string was not recognized for instruments
+    InvalidInstrumentString(String),
+}
+
+#[derive(Debug)]
+pub enum ChordNameError {
+    /// The string was not recognized for chord name preferences
+    InvalidChordNamePreferenceString(String),
+}
+
+#[derive(Debug)]
+pub enum TuningError {
+    /// The string was neither a known tuning nor a list of open string pitches
+    InvalidTuningString(String),
+}
+
+#[derive(Debug)]
+pub enum HandSplitError {
+    /// The string was neither a note, `dynamic` nor `none`
+    InvalidHandSplitString(String),
+}
+
+#[derive(Debug)]
+pub enum KeyswitchError {
+    /// The string was not recognized for keyswitch actions
+    InvalidKeyswitchString(String),
+}
+
+#[derive(Debug)]
+pub enum ModifierError {
+    /// The string is neither `cc<controller>` nor `note<note>`
+    InvalidModifierControlString(String),
+}
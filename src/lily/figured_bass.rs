@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::MidiNote;
+
+use super::{ChordAnalysis, LilyNote, LilyParameters, Note};
+
+/// Figures which are left out when they are implied by the others, given as the full set of
+/// figures and the ones which are written
+const ABBREVIATIONS: [(&[u8], &[u8]); 7] = [
+    (&[3, 5], &[]),
+    (&[3, 6], &[6]),
+    (&[4, 6], &[6, 4]),
+    (&[3, 5, 7], &[7]),
+    (&[3, 5, 6], &[6, 5]),
+    (&[3, 4, 6], &[4, 3]),
+    (&[2, 4, 6], &[4, 2]),
+];
+
+/// The figures of the given notes above their lowest note in LilyPond `\figuremode` syntax,
+/// such as `<6 4>` or `<7 _+>`
+///
+/// Intervals are reduced to within an octave, octave doublings are left out, and the usual
+/// abbreviations are applied (e.g. `<6>` instead of `<6 3>`, and `<_>` for a root position
+/// triad or a single note). Accidentals are given relative to the written key signature: `+`
+/// for sharps, `-` for flats and `!` for naturals. A third which only needs an accidental is
+/// written as `_+`, `_-` or `_!`.
+pub fn figures(notes: &BTreeSet<MidiNote>, parameters: &LilyParameters) -> String {
+    let Some(bass) = notes.first() else {
+        return "<_>".into();
+    };
+    // spell the notes consistently with respect to the chord's root, like in chords
+    let spelling = ChordAnalysis::new(notes)
+        .and_then(|chord| chord.spell(parameters))
+        .unwrap_or_default();
+    let spell = |note: &MidiNote| match parameters.spelling_override(*note) {
+        Some(spelling) => *spelling,
+        None => match spelling.get(&(note % 12)) {
+            Some(spelling) => *spelling,
+            None => LilyNote::spell(*note, parameters),
+        },
+    };
+    let bass_letter = spell(bass).letter();
+    let key = parameters.written_key();
+    // figures mapped to their accidentals
+    let mut figures: BTreeMap<u8, String> = BTreeMap::new();
+    for note in notes.iter().skip(1) {
+        let spelling: Note = spell(note);
+        let figure = match (spelling.letter() + 7 - bass_letter) % 7 {
+            0 => 8,
+            steps => steps + 1,
+        };
+        let accidental = match spelling.alteration() - key.alteration(spelling.letter()) {
+            0 => String::new(),
+            _ => match spelling.alteration() {
+                0 => "!".into(),
+                alteration if alteration > 0 => "+".repeat(alteration as usize),
+                alteration => "-".repeat(alteration.unsigned_abs() as usize),
+            },
+        };
+        figures.entry(figure).or_insert(accidental);
+    }
+    figures.remove(&8);
+    let all: Vec<u8> = figures.keys().copied().collect();
+    let shown: &[u8] = ABBREVIATIONS
+        .iter()
+        .find(|(full, _)| *full == all.as_slice())
+        .map_or(&all, |(_, shown)| shown);
+    let figures: Vec<String> = figures
+        .iter()
+        .rev()
+        .filter(|(figure, accidental)| shown.contains(figure) || !accidental.is_empty())
+        .map(
+            |(figure, accidental)| match shown.contains(figure) || *figure != 3 {
+                true => format!("{figure}{accidental}"),
+                false => format!("_{accidental}"),
+            },
+        )
+        .collect();
+    match figures.is_empty() {
+        true => "<_>".into(),
+        false => format!("<{}>", figures.join(" ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn figures_of(notes: &[MidiNote]) -> String {
+        figures(
+            &notes.iter().copied().collect(),
+            &LilyParameters::for_tests(),
+        )
+    }
+
+    #[test]
+    fn abbreviates_triads_and_inversions() {
+        assert_eq!(figures_of(&[48, 64, 67, 72]), "<_>");
+        assert_eq!(figures_of(&[52, 55, 60]), "<6>");
+        assert_eq!(figures_of(&[55, 60, 64]), "<6 4>");
+        assert_eq!(figures_of(&[60]), "<_>");
+    }
+
+    #[test]
+    fn writes_accidentals_relative_to_the_key() {
+        assert_eq!(figures_of(&[52, 56, 59, 62]), "<7 _+>");
+        assert_eq!(figures_of(&[60, 63, 67]), "<_->");
+        assert_eq!(figures_of(&[50, 54, 57, 60]), "<7 _+>");
+    }
+}
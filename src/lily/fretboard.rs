@@ -0,0 +1,165 @@
+use crate::MidiNote;
+
+use super::TuningError;
+
+make_lily_str_map!(
+    /// Common tunings of fretted instruments
+    Tuning;
+    TuningError::InvalidTuningString;
+    Standard, "standard", "guitar";
+    DropD, "drop-d";
+    Bass, "bass", "bass-4";
+    Bass5, "bass-5";
+);
+
+impl Tuning {
+    /// Pitches of the open strings, from the lowest string to the highest
+    pub fn strings(&self) -> Vec<MidiNote> {
+        match self {
+            Tuning::Standard => vec![40, 45, 50, 55, 59, 64], // e, a, d g b e'
+            Tuning::DropD => vec![38, 45, 50, 55, 59, 64],    // d, a, d g b e'
+            Tuning::Bass => vec![28, 33, 38, 43],             // e,, a,, d, g,
+            Tuning::Bass5 => vec![23, 28, 33, 38, 43],        // b,,, e,, a,, d, g,
+        }
+    }
+}
+
+/// Highest fret which can be played
+const MAX_FRET: u8 = 24;
+/// Number of frets the hand covers in one position without stretching
+const POSITION_SPAN: u8 = 4;
+
+/// Find the strings on which to play the given notes, as string numbers counted from the
+/// highest string (string 1, as in LilyPond's `c\1`) and frets
+///
+/// Every note gets its own string. Open strings are preferred, as are frets close to the
+/// previous position (the lowest fretted fret of the previous notes), such that the hand moves
+/// as little as possible. Without a previous position, low frets are preferred. Chords spanning
+/// more frets than the hand can stretch are not considered playable.
+///
+/// Returns `None` if there is no playable assignment.
+pub fn fingering(
+    notes: &[MidiNote],
+    strings: &[MidiNote],
+    position: Option<u8>,
+) -> Option<Vec<(u8, u8)>> {
+    let mut best: Option<(u32, Vec<(u8, u8)>)> = None;
+    let mut current = Vec::with_capacity(notes.len());
+    search(notes, strings, position, &mut current, &mut best);
+    best.map(|(_, fingering)| fingering)
+}
+
+/// Try all assignments of the remaining notes to unused strings, keeping the cheapest one
+fn search(
+    notes: &[MidiNote],
+    strings: &[MidiNote],
+    position: Option<u8>,
+    current: &mut Vec<(u8, u8)>,
+    best: &mut Option<(u32, Vec<(u8, u8)>)>,
+) {
+    let Some((note, rest)) = notes.split_first() else {
+        if let Some(cost) = cost(current, position) {
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                *best = Some((cost, current.clone()));
+            }
+        }
+        return;
+    };
+    for (index, open) in strings.iter().enumerate() {
+        let string = (strings.len() - index) as u8;
+        if current.iter().any(|(used, _)| *used == string) {
+            continue;
+        }
+        let Some(fret) = note.checked_sub(*open).filter(|fret| *fret <= MAX_FRET) else {
+            continue;
+        };
+        current.push((string, fret));
+        search(rest, strings, position, current, best);
+        current.pop();
+    }
+}
+
+/// How hard it is to play the given strings and frets, or `None` if they cannot be played at once
+fn cost(fingering: &[(u8, u8)], position: Option<u8>) -> Option<u32> {
+    let fretted = fingering
+        .iter()
+        .map(|(_, fret)| *fret)
+        .filter(|fret| *fret > 0);
+    let (Some(lowest), Some(highest)) = (fretted.clone().min(), fretted.clone().max()) else {
+        // only open strings
+        return Some(0);
+    };
+    if highest - lowest > POSITION_SPAN {
+        return None;
+    }
+    let stretch = u32::from((highest - lowest).saturating_sub(POSITION_SPAN - 1));
+    let movement = match position {
+        Some(position) => fretted
+            .map(|fret| match fret {
+                fret if fret < position => u32::from(position - fret),
+                fret if fret >= position + POSITION_SPAN => {
+                    u32::from(fret - (position + POSITION_SPAN - 1))
+                }
+                _ => 0,
+            })
+            .sum(),
+        None => u32::from(lowest),
+    };
+    Some(movement * 4 + stretch * 2 + u32::from(highest))
+}
+
+/// The position of the hand after playing the given strings and frets
+///
+/// The hand stays where it is as long as the fretted frets lie within its span, and otherwise
+/// moves just far enough to reach them. Open strings do not move the hand.
+pub fn hand_position(fingering: &[(u8, u8)], previous: Option<u8>) -> Option<u8> {
+    let fretted = fingering
+        .iter()
+        .map(|(_, fret)| *fret)
+        .filter(|fret| *fret > 0);
+    let (Some(lowest), Some(highest)) = (fretted.clone().min(), fretted.max()) else {
+        return previous;
+    };
+    match previous {
+        Some(position) if lowest >= position && highest < position + POSITION_SPAN => {
+            Some(position)
+        }
+        Some(position) if lowest > position => {
+            Some(highest.saturating_sub(POSITION_SPAN - 1).clamp(1, lowest))
+        }
+        _ => Some(lowest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_open_strings_and_low_frets() {
+        let strings = Tuning::Standard.strings();
+        assert_eq!(fingering(&[64], &strings, None), Some(vec![(1, 0)]));
+        assert_eq!(fingering(&[67], &strings, None), Some(vec![(1, 3)]));
+        assert_eq!(
+            fingering(&[48, 52, 55], &strings, None),
+            Some(vec![(5, 3), (4, 2), (3, 0)])
+        );
+    }
+
+    #[test]
+    fn stays_close_to_the_hand_position() {
+        let strings = Tuning::Standard.strings();
+        assert_eq!(fingering(&[67], &strings, Some(7)), Some(vec![(2, 8)]));
+        assert_eq!(hand_position(&[(2, 8)], Some(7)), Some(7));
+        assert_eq!(hand_position(&[(1, 3)], Some(7)), Some(3));
+        assert_eq!(hand_position(&[(1, 0)], Some(7)), Some(7));
+    }
+
+    #[test]
+    fn rejects_unplayable_notes() {
+        let strings = Tuning::Standard.strings();
+        // both notes can only be played on the lowest string
+        assert_eq!(fingering(&[40, 41], &strings, None), None);
+        assert_eq!(fingering(&[30], &strings, None), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeSet;
+
+use crate::MidiNote;
+
+use super::{Clef, Note};
+
+/// The hands of a piano part, each written on its own staff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Right,
+    Left,
+}
+
+/// How notes are split between the hands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandSplit {
+    /// Notes from the given note upwards go to the right hand, lower notes to the left hand
+    Fixed(MidiNote),
+    /// Notes are grouped into what each hand can reach, close to where the hands last played
+    Dynamic,
+}
+
+/// Largest interval in semitones which one hand is assumed to reach (a ninth)
+const HAND_SPAN: MidiNote = 14;
+
+/// Where the hands are assumed to be before they played anything
+const DEFAULT_CENTERS: [MidiNote; 2] = [67, 53];
+
+/// The state of the hand whose notes are not being output, which is swapped with the state
+/// in the parameters when switching hands
+#[derive(Debug, Clone)]
+pub struct HandState {
+    pub(super) previous_absolute_note_reference: Option<MidiNote>,
+    pub(super) previous_absolute_note_reference_spelling: Option<Note>,
+    pub(super) octave_check_on_next_note: bool,
+    pub(super) notes_since_octave_check: u8,
+    pub(super) clef: Clef,
+}
+
+impl Default for HandState {
+    /// The left hand before it played anything, written in the bass clef
+    fn default() -> Self {
+        Self {
+            previous_absolute_note_reference: None,
+            previous_absolute_note_reference_spelling: None,
+            octave_check_on_next_note: false,
+            notes_since_octave_check: 0,
+            clef: Clef::Bass,
+        }
+    }
+}
+
+/// Split the given notes into the notes for the right hand and the notes for the left hand
+///
+/// With a dynamic split, the notes are divided where both hands can reach their notes, such
+/// that the notes are as close as possible to the centers of the notes each hand played last
+/// (given for the right and the left hand). If the notes cannot be reached by two hands, they
+/// are divided at the largest interval between them.
+pub fn split_hands(
+    notes: &BTreeSet<MidiNote>,
+    split: &HandSplit,
+    centers: [Option<MidiNote>; 2],
+) -> (BTreeSet<MidiNote>, BTreeSet<MidiNote>) {
+    let sorted: Vec<MidiNote> = notes.iter().copied().collect();
+    let index = match split {
+        HandSplit::Fixed(split) => sorted.partition_point(|note| note < split),
+        HandSplit::Dynamic => {
+            let [right, left] = [0, 1].map(|i| centers[i].unwrap_or(DEFAULT_CENTERS[i]));
+            let span = |notes: &[MidiNote]| match (notes.first(), notes.last()) {
+                (Some(lowest), Some(highest)) => highest - lowest,
+                _ => 0,
+            };
+            let distance = |notes: &[MidiNote], center: MidiNote| -> u32 {
+                notes
+                    .iter()
+                    .map(|note| u32::from(note.abs_diff(center)))
+                    .sum()
+            };
+            // the left hand plays the notes below the index, the right hand the others
+            (0..=sorted.len())
+                .filter(|i| span(&sorted[..*i]) <= HAND_SPAN && span(&sorted[*i..]) <= HAND_SPAN)
+                .min_by_key(|i| distance(&sorted[..*i], left) + distance(&sorted[*i..], right))
+                .unwrap_or_else(|| {
+                    (1..sorted.len())
+                        .max_by_key(|i| sorted[*i] - sorted[*i - 1])
+                        .unwrap_or(0)
+                })
+        }
+    };
+    let (left, right) = sorted.split_at(index);
+    (
+        right.iter().copied().collect(),
+        left.iter().copied().collect(),
+    )
+}
+
+/// The center of the given notes, around which the hand playing them is assumed to be
+pub fn hand_center(notes: &BTreeSet<MidiNote>) -> Option<MidiNote> {
+    let sum: u32 = notes.iter().map(|note| u32::from(*note)).sum();
+    (!notes.is_empty()).then(|| (sum / notes.len() as u32) as MidiNote)
+}
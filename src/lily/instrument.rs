@@ -0,0 +1,84 @@
+use crate::MidiNote;
+
+use super::{Clef, InstrumentError};
+
+make_lily_str_map!(
+    /// Instruments (and voices) with a known range
+    Instrument;
+    InstrumentError::InvalidInstrumentString;
+    Piano, "piano";
+    Violin, "violin";
+    Viola, "viola";
+    Cello, "cello";
+    DoubleBass, "double-bass", "contrabass";
+    Guitar, "guitar";
+    Piccolo, "piccolo";
+    Flute, "flute";
+    Oboe, "oboe";
+    Clarinet, "clarinet";
+    Bassoon, "bassoon";
+    AltoSaxophone, "alto-saxophone", "alto-sax";
+    TenorSaxophone, "tenor-saxophone", "tenor-sax";
+    Trumpet, "trumpet";
+    Horn, "horn";
+    Trombone, "trombone";
+    Tuba, "tuba";
+    Soprano, "soprano";
+    Alto, "alto";
+    Tenor, "tenor";
+    Bass, "bass";
+);
+
+impl Instrument {
+    /// Lowest and highest playable notes at concert pitch (inclusive)
+    pub fn range(&self) -> (MidiNote, MidiNote) {
+        match self {
+            Instrument::Piano => (21, 108),         // A0 - C8
+            Instrument::Violin => (55, 100),        // G3 - E7
+            Instrument::Viola => (48, 88),          // C3 - E6
+            Instrument::Cello => (36, 81),          // C2 - A5
+            Instrument::DoubleBass => (28, 67),     // E1 - G4
+            Instrument::Guitar => (40, 83),         // E2 - B5
+            Instrument::Piccolo => (74, 108),       // D5 - C8
+            Instrument::Flute => (60, 96),          // C4 - C7
+            Instrument::Oboe => (58, 93),           // B♭3 - A6
+            Instrument::Clarinet => (50, 94),       // D3 - B♭6
+            Instrument::Bassoon => (34, 75),        // B♭1 - E♭5
+            Instrument::AltoSaxophone => (49, 80),  // D♭3 - A♭5
+            Instrument::TenorSaxophone => (44, 75), // A♭2 - E♭5
+            Instrument::Trumpet => (52, 82),        // E3 - B♭5
+            Instrument::Horn => (35, 77),           // B1 - F5
+            Instrument::Trombone => (40, 72),       // E2 - C5
+            Instrument::Tuba => (26, 65),           // D1 - F4
+            Instrument::Soprano => (60, 84),        // C4 - C6
+            Instrument::Alto => (53, 77),           // F3 - F5
+            Instrument::Tenor => (48, 72),          // C3 - C5
+            Instrument::Bass => (40, 64),           // E2 - E4
+        }
+    }
+
+    /// The clefs the instrument is commonly written in, used for automatic clef switching
+    pub fn clefs(&self) -> Vec<Clef> {
+        match self {
+            Instrument::Piano | Instrument::Horn => vec![Clef::Bass, Clef::Treble],
+            Instrument::Viola => vec![Clef::Alto, Clef::Treble],
+            Instrument::Cello => vec![Clef::Bass, Clef::Tenor, Clef::Treble],
+            Instrument::DoubleBass | Instrument::Bassoon | Instrument::Trombone => {
+                vec![Clef::Bass, Clef::Tenor]
+            }
+            Instrument::Tuba | Instrument::Bass => vec![Clef::Bass],
+            Instrument::Violin
+            | Instrument::Guitar
+            | Instrument::Piccolo
+            | Instrument::Flute
+            | Instrument::Oboe
+            | Instrument::Clarinet
+            | Instrument::AltoSaxophone
+            | Instrument::TenorSaxophone
+            | Instrument::Trumpet
+            | Instrument::Soprano
+            | Instrument::Alto
+            | Instrument::Tenor => vec![Clef::Treble],
+        }
+    }
+}
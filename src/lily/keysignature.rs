@@ -35,3 +35,95 @@ make_lily_str_map!(
     DSharpMinor, "dism" ; // 6 sharps
     ASharpMinor, "aism" ; // 7 sharps
 );
+
+impl LilyKeySignature {
+    /// Number of sharps (positive) or flats (negative) in the key signature
+    pub fn fifths(&self) -> i8 {
+        use LilyKeySignature::*;
+        match self {
+            CFlatMajor | AFlatMinor => -7,
+            GFlatMajor | EFlatMinor => -6,
+            DFlatMajor | BFlatMinor => -5,
+            AFlatMajor | FMinor => -4,
+            EFlatMajor | CMinor => -3,
+            BFlatMajor | GMinor => -2,
+            FMajor | DMinor => -1,
+            CMajor | AMinor => 0,
+            GMajor | EMinor => 1,
+            DMajor | BMinor => 2,
+            AMajor | FSharpMinor => 3,
+            EMajor | CSharpMinor => 4,
+            BMajor | GSharpMinor => 5,
+            FSharpMajor | DSharpMinor => 6,
+            CSharpMajor | ASharpMinor => 7,
+        }
+    }
+
+    /// Whether this is a minor key
+    pub fn is_minor(&self) -> bool {
+        use LilyKeySignature::*;
+        matches!(
+            self,
+            AFlatMinor
+                | EFlatMinor
+                | BFlatMinor
+                | FMinor
+                | CMinor
+                | GMinor
+                | DMinor
+                | AMinor
+                | EMinor
+                | BMinor
+                | FSharpMinor
+                | CSharpMinor
+                | GSharpMinor
+                | DSharpMinor
+                | ASharpMinor
+        )
+    }
+
+    /// Get the major or minor key with the given number of sharps (positive) or flats (negative)
+    ///
+    /// Returns `None` if there are more than 7 sharps or flats.
+    pub fn from_fifths(fifths: i8, minor: bool) -> Option<Self> {
+        <Self as clap::ValueEnum>::value_variants()
+            .iter()
+            .find(|key| key.fifths() == fifths && key.is_minor() == minor)
+            .cloned()
+    }
+
+    /// Transpose the key by the given number of fifths (i.e. 2 for a major second up)
+    ///
+    /// Keys which would have more than 7 sharps or flats are replaced by their enharmonic
+    /// equivalent.
+    pub fn transpose(&self, fifths: i8) -> Self {
+        let mut fifths = self.fifths() as i16 + fifths as i16;
+        while fifths > 7 {
+            fifths -= 12;
+        }
+        while fifths < -7 {
+            fifths += 12;
+        }
+        Self::from_fifths(fifths as i8, self.is_minor()).expect("Key is within 7 sharps or flats")
+    }
+
+    /// The alteration in semitones which the key signature applies to the given note letter
+    /// (C=0, D=1, ..., B=6)
+    pub fn alteration(&self, letter: u8) -> i8 {
+        // order in which sharps are added to a key signature: F C G D A E B
+        // (flats are added in the reverse order)
+        const SHARPS: [u8; 7] = [3, 0, 4, 1, 5, 2, 6];
+        let fifths = self.fifths();
+        let position = SHARPS
+            .iter()
+            .position(|l| *l == letter % 7)
+            .expect("Letter is within an octave") as i8;
+        if fifths > position {
+            1
+        } else if -fifths > 6 - position {
+            -1
+        } else {
+            0
+        }
+    }
+}
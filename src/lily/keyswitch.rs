@@ -0,0 +1,25 @@
+use super::KeyswitchError;
+
+make_lily_str_map!(
+    /// Actions of keys which are reserved as command keys instead of producing notes
+    KeyswitchAction;
+    KeyswitchError::InvalidKeyswitchString;
+    /// Output a rest
+    Rest, "rest", "r";
+    /// Output a skip
+    Skip, "skip", "s";
+    /// Output a full bar rest
+    FullBarRest, "full-bar-rest", "R";
+    /// Output a bar check
+    BarCheck, "bar-check", "|";
+    /// Take back the previous output
+    Undo, "undo", "u";
+    /// Switch between entering single notes and chords
+    ToggleChords, "toggle-chords", "tc";
+    /// Switch to the next kind of accidentals for out of key notes
+    CycleAccidentals, "cycle-accidentals", "ca";
+    /// Shift the following notes up by an octave
+    OctaveUp, "octave-up", "ou";
+    /// Shift the following notes down by an octave
+    OctaveDown, "octave-down", "od";
+);
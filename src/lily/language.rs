@@ -1,3 +1,5 @@
+use crate::MidiNote;
+
 use super::LilypondLanguageError;
 
 make_lily_str_map!(
@@ -20,6 +22,8 @@ make_lily_str_map!(
     Suomi, "suomi";
     Svenska, "svenska";
     Vlaams, "vlaams";
+    /// Note names from LilyPond's `arabic.ly`, which are the Italian ones with quarter tones
+    Arabic, "arabic";
 );
 
 impl Language {
@@ -27,348 +31,968 @@ impl Language {
         match self {
             Language::Nederlands => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "ceses",
                 Note::CFlat => "ces",
                 Note::CSharp => "cis",
                 Note::CSharpSharp => "cisis",
                 Note::D => "d",
+                Note::DFlatFlat => "deses",
                 Note::DFlat => "des",
                 Note::DSharp => "dis",
+                Note::DSharpSharp => "disis",
                 Note::E => "e",
+                Note::EFlatFlat => "eeses",
                 Note::EFlat => "ees",
                 Note::ESharp => "eis",
+                Note::ESharpSharp => "eisis",
                 Note::F => "f",
+                Note::FFlatFlat => "feses",
                 Note::FFlat => "fes",
                 Note::FSharp => "fis",
                 Note::FSharpSharp => "fisis",
                 Note::G => "g",
+                Note::GFlatFlat => "geses",
                 Note::GFlat => "ges",
                 Note::GSharp => "gis",
                 Note::GSharpSharp => "gisis",
                 Note::A => "a",
+                Note::AFlatFlat => "aeses",
                 Note::AFlat => "aes",
                 Note::ASharp => "ais",
+                Note::ASharpSharp => "aisis",
                 Note::B => "b",
+                Note::BFlatFlat => "beses",
                 Note::BFlat => "bes",
                 Note::BSharp => "bis",
+                Note::BSharpSharp => "bisis",
             },
             Language::Catalan => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dod",
                 Note::CSharpSharp => "dodd",
                 Note::D => "re",
+                Note::DFlatFlat => "rebb",
                 Note::DFlat => "reb",
                 Note::DSharp => "red",
+                Note::DSharpSharp => "redd",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mid",
+                Note::ESharpSharp => "midd",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fad",
                 Note::FSharpSharp => "fadd",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "sold",
                 Note::GSharpSharp => "soldd",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "lad",
+                Note::ASharpSharp => "ladd",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sid",
+                Note::BSharpSharp => "sidd",
             },
             Language::Deutsch => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "ceses",
                 Note::CFlat => "ces",
                 Note::CSharp => "cis",
                 Note::CSharpSharp => "cisis",
                 Note::D => "d",
+                Note::DFlatFlat => "deses",
                 Note::DFlat => "des",
                 Note::DSharp => "dis",
+                Note::DSharpSharp => "disis",
                 Note::E => "e",
+                Note::EFlatFlat => "eses",
                 Note::EFlat => "es",
                 Note::ESharp => "eis",
+                Note::ESharpSharp => "eisis",
                 Note::F => "f",
+                Note::FFlatFlat => "feses",
                 Note::FFlat => "fes",
                 Note::FSharp => "fis",
                 Note::FSharpSharp => "fisis",
                 Note::G => "g",
+                Note::GFlatFlat => "geses",
                 Note::GFlat => "ges",
                 Note::GSharp => "gis",
                 Note::GSharpSharp => "gisis",
                 Note::A => "a",
+                Note::AFlatFlat => "asas",
                 Note::AFlat => "as",
                 Note::ASharp => "ais",
+                Note::ASharpSharp => "aisis",
                 Note::B => "h",
+                Note::BFlatFlat => "heses",
                 Note::BFlat => "b",
                 Note::BSharp => "his",
+                Note::BSharpSharp => "hisis",
             },
             Language::English => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "cff",
                 Note::CFlat => "cf",
                 Note::CSharp => "cs",
                 Note::CSharpSharp => "css",
                 Note::D => "d",
+                Note::DFlatFlat => "dff",
                 Note::DFlat => "df",
                 Note::DSharp => "ds",
+                Note::DSharpSharp => "dss",
                 Note::E => "e",
+                Note::EFlatFlat => "eff",
                 Note::EFlat => "ef",
                 Note::ESharp => "es",
+                Note::ESharpSharp => "ess",
                 Note::F => "f",
+                Note::FFlatFlat => "fff",
                 Note::FFlat => "ff",
                 Note::FSharp => "fs",
                 Note::FSharpSharp => "fss",
                 Note::G => "g",
+                Note::GFlatFlat => "gff",
                 Note::GFlat => "gf",
                 Note::GSharp => "gs",
                 Note::GSharpSharp => "gss",
                 Note::A => "a",
+                Note::AFlatFlat => "aff",
                 Note::AFlat => "af",
                 Note::ASharp => "as",
+                Note::ASharpSharp => "ass",
                 Note::B => "b",
+                Note::BFlatFlat => "bff",
                 Note::BFlat => "bf",
                 Note::BSharp => "bs",
+                Note::BSharpSharp => "bss",
             },
             Language::Espanol => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dos",
                 Note::CSharpSharp => "doss",
                 Note::D => "re",
+                Note::DFlatFlat => "rebb",
                 Note::DFlat => "reb",
                 Note::DSharp => "res",
+                Note::DSharpSharp => "ress",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mis",
+                Note::ESharpSharp => "miss",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fas",
                 Note::FSharpSharp => "fass",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "sols",
                 Note::GSharpSharp => "solss",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "las",
+                Note::ASharpSharp => "lass",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sis",
+                Note::BSharpSharp => "siss",
             },
             Language::Francais => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dod",
                 Note::CSharpSharp => "dodd",
                 Note::D => "ré",
+                Note::DFlatFlat => "rébb",
                 Note::DFlat => "réb",
                 Note::DSharp => "réd",
+                Note::DSharpSharp => "rédd",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mid",
+                Note::ESharpSharp => "midd",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fad",
                 Note::FSharpSharp => "fadd",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "sold",
                 Note::GSharpSharp => "soldd",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "lad",
+                Note::ASharpSharp => "ladd",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sid",
+                Note::BSharpSharp => "sidd",
             },
-            Language::Italiano => match note {
+            Language::Italiano | Language::Arabic => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dod",
                 Note::CSharpSharp => "dodd",
                 Note::D => "re",
+                Note::DFlatFlat => "rebb",
                 Note::DFlat => "reb",
                 Note::DSharp => "red",
+                Note::DSharpSharp => "redd",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mid",
+                Note::ESharpSharp => "midd",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fad",
                 Note::FSharpSharp => "fadd",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "sold",
                 Note::GSharpSharp => "soldd",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "lad",
+                Note::ASharpSharp => "ladd",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sid",
+                Note::BSharpSharp => "sidd",
             },
             Language::Norsk => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "cessess",
                 Note::CFlat => "cess",
                 Note::CSharp => "ciss",
                 Note::CSharpSharp => "cississ",
                 Note::D => "d",
+                Note::DFlatFlat => "dessess",
                 Note::DFlat => "dess",
                 Note::DSharp => "diss",
+                Note::DSharpSharp => "dississ",
                 Note::E => "e",
+                Note::EFlatFlat => "essess",
                 Note::EFlat => "ess",
                 Note::ESharp => "eiss",
+                Note::ESharpSharp => "eississ",
                 Note::F => "f",
+                Note::FFlatFlat => "fessess",
                 Note::FFlat => "fess",
                 Note::FSharp => "fiss",
                 Note::FSharpSharp => "fississ",
                 Note::G => "g",
+                Note::GFlatFlat => "gessess",
                 Note::GFlat => "gess",
                 Note::GSharp => "giss",
                 Note::GSharpSharp => "gississ",
                 Note::A => "a",
+                Note::AFlatFlat => "assess",
                 Note::AFlat => "ass",
                 Note::ASharp => "aiss",
+                Note::ASharpSharp => "aississ",
                 Note::B => "h",
+                Note::BFlatFlat => "hessess",
                 Note::BFlat => "b",
                 Note::BSharp => "hiss",
+                Note::BSharpSharp => "hississ",
             },
             Language::Portugues => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dos",
                 Note::CSharpSharp => "doss",
                 Note::D => "re",
+                Note::DFlatFlat => "rebb",
                 Note::DFlat => "reb",
                 Note::DSharp => "res",
+                Note::DSharpSharp => "ress",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mis",
+                Note::ESharpSharp => "miss",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fas",
                 Note::FSharpSharp => "fass",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "sols",
                 Note::GSharpSharp => "solss",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "las",
+                Note::ASharpSharp => "lass",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sis",
+                Note::BSharpSharp => "siss",
             },
             Language::Suomi => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "ceses",
                 Note::CFlat => "ces",
                 Note::CSharp => "cis",
                 Note::CSharpSharp => "cisis",
                 Note::D => "d",
+                Note::DFlatFlat => "deses",
                 Note::DFlat => "des",
                 Note::DSharp => "dis",
+                Note::DSharpSharp => "disis",
                 Note::E => "e",
+                Note::EFlatFlat => "eses",
                 Note::EFlat => "es",
                 Note::ESharp => "eis",
+                Note::ESharpSharp => "eisis",
                 Note::F => "f",
+                Note::FFlatFlat => "feses",
                 Note::FFlat => "fes",
                 Note::FSharp => "fis",
                 Note::FSharpSharp => "fisis",
                 Note::G => "g",
+                Note::GFlatFlat => "geses",
                 Note::GFlat => "ges",
                 Note::GSharp => "gis",
                 Note::GSharpSharp => "gisis",
                 Note::A => "a",
+                Note::AFlatFlat => "asas",
                 Note::AFlat => "as",
                 Note::ASharp => "ais",
+                Note::ASharpSharp => "aisis",
                 Note::B => "h",
+                Note::BFlatFlat => "heses",
                 Note::BFlat => "b",
                 Note::BSharp => "his",
+                Note::BSharpSharp => "hisis",
             },
             Language::Svenska => match note {
                 Note::C => "c",
+                Note::CFlatFlat => "cessess",
                 Note::CFlat => "cess",
                 Note::CSharp => "ciss",
                 Note::CSharpSharp => "cississ",
                 Note::D => "d",
+                Note::DFlatFlat => "dessess",
                 Note::DFlat => "dess",
                 Note::DSharp => "diss",
+                Note::DSharpSharp => "dississ",
                 Note::E => "e",
+                Note::EFlatFlat => "essess",
                 Note::EFlat => "ess",
                 Note::ESharp => "eiss",
+                Note::ESharpSharp => "eississ",
                 Note::F => "f",
+                Note::FFlatFlat => "fessess",
                 Note::FFlat => "fess",
                 Note::FSharp => "fiss",
                 Note::FSharpSharp => "fississ",
                 Note::G => "g",
+                Note::GFlatFlat => "gessess",
                 Note::GFlat => "gess",
                 Note::GSharp => "giss",
                 Note::GSharpSharp => "gississ",
                 Note::A => "a",
+                Note::AFlatFlat => "assess",
                 Note::AFlat => "ass",
                 Note::ASharp => "aiss",
+                Note::ASharpSharp => "aississ",
                 Note::B => "h",
+                Note::BFlatFlat => "hessess",
                 Note::BFlat => "b",
                 Note::BSharp => "hiss",
+                Note::BSharpSharp => "hississ",
             },
             Language::Vlaams => match note {
                 Note::C => "do",
+                Note::CFlatFlat => "dobb",
                 Note::CFlat => "dob",
                 Note::CSharp => "dok",
                 Note::CSharpSharp => "dokk",
                 Note::D => "re",
+                Note::DFlatFlat => "rebb",
                 Note::DFlat => "reb",
                 Note::DSharp => "rek",
+                Note::DSharpSharp => "rekk",
                 Note::E => "mi",
+                Note::EFlatFlat => "mibb",
                 Note::EFlat => "mib",
                 Note::ESharp => "mik",
+                Note::ESharpSharp => "mikk",
                 Note::F => "fa",
+                Note::FFlatFlat => "fabb",
                 Note::FFlat => "fab",
                 Note::FSharp => "fak",
                 Note::FSharpSharp => "fakk",
                 Note::G => "sol",
+                Note::GFlatFlat => "solbb",
                 Note::GFlat => "solb",
                 Note::GSharp => "solk",
                 Note::GSharpSharp => "solkk",
                 Note::A => "la",
+                Note::AFlatFlat => "labb",
                 Note::AFlat => "lab",
                 Note::ASharp => "lak",
+                Note::ASharpSharp => "lakk",
                 Note::B => "si",
+                Note::BFlatFlat => "sibb",
                 Note::BFlat => "sib",
                 Note::BSharp => "sik",
+                Note::BSharpSharp => "sikk",
             },
         }
     }
 }
 
+impl Language {
+    /// Get the name of a note with a quarter tone alteration
+    ///
+    /// Returns `None` if the language has no names for quarter tones.
+    pub fn quarter_tone_to_str(&self, note: &QuarterToneNote) -> Option<&'static str> {
+        match self {
+            Language::Nederlands => Some(match note {
+                QuarterToneNote::CSesquiFlat => "ceseh",
+                QuarterToneNote::CSemiFlat => "ceh",
+                QuarterToneNote::CSemiSharp => "cih",
+                QuarterToneNote::CSesquiSharp => "cisih",
+                QuarterToneNote::DSesquiFlat => "deseh",
+                QuarterToneNote::DSemiFlat => "deh",
+                QuarterToneNote::DSemiSharp => "dih",
+                QuarterToneNote::DSesquiSharp => "disih",
+                QuarterToneNote::ESesquiFlat => "eeseh",
+                QuarterToneNote::ESemiFlat => "eeh",
+                QuarterToneNote::ESemiSharp => "eih",
+                QuarterToneNote::ESesquiSharp => "eisih",
+                QuarterToneNote::FSesquiFlat => "feseh",
+                QuarterToneNote::FSemiFlat => "feh",
+                QuarterToneNote::FSemiSharp => "fih",
+                QuarterToneNote::FSesquiSharp => "fisih",
+                QuarterToneNote::GSesquiFlat => "geseh",
+                QuarterToneNote::GSemiFlat => "geh",
+                QuarterToneNote::GSemiSharp => "gih",
+                QuarterToneNote::GSesquiSharp => "gisih",
+                QuarterToneNote::ASesquiFlat => "aeseh",
+                QuarterToneNote::ASemiFlat => "aeh",
+                QuarterToneNote::ASemiSharp => "aih",
+                QuarterToneNote::ASesquiSharp => "aisih",
+                QuarterToneNote::BSesquiFlat => "beseh",
+                QuarterToneNote::BSemiFlat => "beh",
+                QuarterToneNote::BSemiSharp => "bih",
+                QuarterToneNote::BSesquiSharp => "bisih",
+            }),
+            Language::Deutsch => Some(match note {
+                QuarterToneNote::CSesquiFlat => "ceseh",
+                QuarterToneNote::CSemiFlat => "ceh",
+                QuarterToneNote::CSemiSharp => "cih",
+                QuarterToneNote::CSesquiSharp => "cisih",
+                QuarterToneNote::DSesquiFlat => "deseh",
+                QuarterToneNote::DSemiFlat => "deh",
+                QuarterToneNote::DSemiSharp => "dih",
+                QuarterToneNote::DSesquiSharp => "disih",
+                QuarterToneNote::ESesquiFlat => "eseh",
+                QuarterToneNote::ESemiFlat => "eeh",
+                QuarterToneNote::ESemiSharp => "eih",
+                QuarterToneNote::ESesquiSharp => "eisih",
+                QuarterToneNote::FSesquiFlat => "feseh",
+                QuarterToneNote::FSemiFlat => "feh",
+                QuarterToneNote::FSemiSharp => "fih",
+                QuarterToneNote::FSesquiSharp => "fisih",
+                QuarterToneNote::GSesquiFlat => "geseh",
+                QuarterToneNote::GSemiFlat => "geh",
+                QuarterToneNote::GSemiSharp => "gih",
+                QuarterToneNote::GSesquiSharp => "gisih",
+                QuarterToneNote::ASesquiFlat => "aseh",
+                QuarterToneNote::ASemiFlat => "aeh",
+                QuarterToneNote::ASemiSharp => "aih",
+                QuarterToneNote::ASesquiSharp => "aisih",
+                QuarterToneNote::BSesquiFlat => "beh",
+                QuarterToneNote::BSemiFlat => "heh",
+                QuarterToneNote::BSemiSharp => "hih",
+                QuarterToneNote::BSesquiSharp => "hisih",
+            }),
+            Language::English => Some(match note {
+                QuarterToneNote::CSesquiFlat => "ctqf",
+                QuarterToneNote::CSemiFlat => "cqf",
+                QuarterToneNote::CSemiSharp => "cqs",
+                QuarterToneNote::CSesquiSharp => "ctqs",
+                QuarterToneNote::DSesquiFlat => "dtqf",
+                QuarterToneNote::DSemiFlat => "dqf",
+                QuarterToneNote::DSemiSharp => "dqs",
+                QuarterToneNote::DSesquiSharp => "dtqs",
+                QuarterToneNote::ESesquiFlat => "etqf",
+                QuarterToneNote::ESemiFlat => "eqf",
+                QuarterToneNote::ESemiSharp => "eqs",
+                QuarterToneNote::ESesquiSharp => "etqs",
+                QuarterToneNote::FSesquiFlat => "ftqf",
+                QuarterToneNote::FSemiFlat => "fqf",
+                QuarterToneNote::FSemiSharp => "fqs",
+                QuarterToneNote::FSesquiSharp => "ftqs",
+                QuarterToneNote::GSesquiFlat => "gtqf",
+                QuarterToneNote::GSemiFlat => "gqf",
+                QuarterToneNote::GSemiSharp => "gqs",
+                QuarterToneNote::GSesquiSharp => "gtqs",
+                QuarterToneNote::ASesquiFlat => "atqf",
+                QuarterToneNote::ASemiFlat => "aqf",
+                QuarterToneNote::ASemiSharp => "aqs",
+                QuarterToneNote::ASesquiSharp => "atqs",
+                QuarterToneNote::BSesquiFlat => "btqf",
+                QuarterToneNote::BSemiFlat => "bqf",
+                QuarterToneNote::BSemiSharp => "bqs",
+                QuarterToneNote::BSesquiSharp => "btqs",
+            }),
+            Language::Espanol => Some(match note {
+                QuarterToneNote::CSesquiFlat => "dotcb",
+                QuarterToneNote::CSemiFlat => "docb",
+                QuarterToneNote::CSemiSharp => "docs",
+                QuarterToneNote::CSesquiSharp => "dotcs",
+                QuarterToneNote::DSesquiFlat => "retcb",
+                QuarterToneNote::DSemiFlat => "recb",
+                QuarterToneNote::DSemiSharp => "recs",
+                QuarterToneNote::DSesquiSharp => "retcs",
+                QuarterToneNote::ESesquiFlat => "mitcb",
+                QuarterToneNote::ESemiFlat => "micb",
+                QuarterToneNote::ESemiSharp => "mics",
+                QuarterToneNote::ESesquiSharp => "mitcs",
+                QuarterToneNote::FSesquiFlat => "fatcb",
+                QuarterToneNote::FSemiFlat => "facb",
+                QuarterToneNote::FSemiSharp => "facs",
+                QuarterToneNote::FSesquiSharp => "fatcs",
+                QuarterToneNote::GSesquiFlat => "soltcb",
+                QuarterToneNote::GSemiFlat => "solcb",
+                QuarterToneNote::GSemiSharp => "solcs",
+                QuarterToneNote::GSesquiSharp => "soltcs",
+                QuarterToneNote::ASesquiFlat => "latcb",
+                QuarterToneNote::ASemiFlat => "lacb",
+                QuarterToneNote::ASemiSharp => "lacs",
+                QuarterToneNote::ASesquiSharp => "latcs",
+                QuarterToneNote::BSesquiFlat => "sitcb",
+                QuarterToneNote::BSemiFlat => "sicb",
+                QuarterToneNote::BSemiSharp => "sics",
+                QuarterToneNote::BSesquiSharp => "sitcs",
+            }),
+            Language::Francais => Some(match note {
+                QuarterToneNote::CSesquiFlat => "dobsb",
+                QuarterToneNote::CSemiFlat => "dosb",
+                QuarterToneNote::CSemiSharp => "dosd",
+                QuarterToneNote::CSesquiSharp => "dodsd",
+                QuarterToneNote::DSesquiFlat => "rébsb",
+                QuarterToneNote::DSemiFlat => "résb",
+                QuarterToneNote::DSemiSharp => "résd",
+                QuarterToneNote::DSesquiSharp => "rédsd",
+                QuarterToneNote::ESesquiFlat => "mibsb",
+                QuarterToneNote::ESemiFlat => "misb",
+                QuarterToneNote::ESemiSharp => "misd",
+                QuarterToneNote::ESesquiSharp => "midsd",
+                QuarterToneNote::FSesquiFlat => "fabsb",
+                QuarterToneNote::FSemiFlat => "fasb",
+                QuarterToneNote::FSemiSharp => "fasd",
+                QuarterToneNote::FSesquiSharp => "fadsd",
+                QuarterToneNote::GSesquiFlat => "solbsb",
+                QuarterToneNote::GSemiFlat => "solsb",
+                QuarterToneNote::GSemiSharp => "solsd",
+                QuarterToneNote::GSesquiSharp => "soldsd",
+                QuarterToneNote::ASesquiFlat => "labsb",
+                QuarterToneNote::ASemiFlat => "lasb",
+                QuarterToneNote::ASemiSharp => "lasd",
+                QuarterToneNote::ASesquiSharp => "ladsd",
+                QuarterToneNote::BSesquiFlat => "sibsb",
+                QuarterToneNote::BSemiFlat => "sisb",
+                QuarterToneNote::BSemiSharp => "sisd",
+                QuarterToneNote::BSesquiSharp => "sidsd",
+            }),
+            Language::Italiano | Language::Arabic => Some(match note {
+                QuarterToneNote::CSesquiFlat => "dobsb",
+                QuarterToneNote::CSemiFlat => "dosb",
+                QuarterToneNote::CSemiSharp => "dosd",
+                QuarterToneNote::CSesquiSharp => "dodsd",
+                QuarterToneNote::DSesquiFlat => "rebsb",
+                QuarterToneNote::DSemiFlat => "resb",
+                QuarterToneNote::DSemiSharp => "resd",
+                QuarterToneNote::DSesquiSharp => "redsd",
+                QuarterToneNote::ESesquiFlat => "mibsb",
+                QuarterToneNote::ESemiFlat => "misb",
+                QuarterToneNote::ESemiSharp => "misd",
+                QuarterToneNote::ESesquiSharp => "midsd",
+                QuarterToneNote::FSesquiFlat => "fabsb",
+                QuarterToneNote::FSemiFlat => "fasb",
+                QuarterToneNote::FSemiSharp => "fasd",
+                QuarterToneNote::FSesquiSharp => "fadsd",
+                QuarterToneNote::GSesquiFlat => "solbsb",
+                QuarterToneNote::GSemiFlat => "solsb",
+                QuarterToneNote::GSemiSharp => "solsd",
+                QuarterToneNote::GSesquiSharp => "soldsd",
+                QuarterToneNote::ASesquiFlat => "labsb",
+                QuarterToneNote::ASemiFlat => "lasb",
+                QuarterToneNote::ASemiSharp => "lasd",
+                QuarterToneNote::ASesquiSharp => "ladsd",
+                QuarterToneNote::BSesquiFlat => "sibsb",
+                QuarterToneNote::BSemiFlat => "sisb",
+                QuarterToneNote::BSemiSharp => "sisd",
+                QuarterToneNote::BSesquiSharp => "sidsd",
+            }),
+            Language::Portugues => Some(match note {
+                QuarterToneNote::CSesquiFlat => "dobtqt",
+                QuarterToneNote::CSemiFlat => "dobqt",
+                QuarterToneNote::CSemiSharp => "dosqt",
+                QuarterToneNote::CSesquiSharp => "dostqt",
+                QuarterToneNote::DSesquiFlat => "rebtqt",
+                QuarterToneNote::DSemiFlat => "rebqt",
+                QuarterToneNote::DSemiSharp => "resqt",
+                QuarterToneNote::DSesquiSharp => "restqt",
+                QuarterToneNote::ESesquiFlat => "mibtqt",
+                QuarterToneNote::ESemiFlat => "mibqt",
+                QuarterToneNote::ESemiSharp => "misqt",
+                QuarterToneNote::ESesquiSharp => "mistqt",
+                QuarterToneNote::FSesquiFlat => "fabtqt",
+                QuarterToneNote::FSemiFlat => "fabqt",
+                QuarterToneNote::FSemiSharp => "fasqt",
+                QuarterToneNote::FSesquiSharp => "fastqt",
+                QuarterToneNote::GSesquiFlat => "solbtqt",
+                QuarterToneNote::GSemiFlat => "solbqt",
+                QuarterToneNote::GSemiSharp => "solsqt",
+                QuarterToneNote::GSesquiSharp => "solstqt",
+                QuarterToneNote::ASesquiFlat => "labtqt",
+                QuarterToneNote::ASemiFlat => "labqt",
+                QuarterToneNote::ASemiSharp => "lasqt",
+                QuarterToneNote::ASesquiSharp => "lastqt",
+                QuarterToneNote::BSesquiFlat => "sibtqt",
+                QuarterToneNote::BSemiFlat => "sibqt",
+                QuarterToneNote::BSemiSharp => "sisqt",
+                QuarterToneNote::BSesquiSharp => "sistqt",
+            }),
+            Language::Catalan
+            | Language::Norsk
+            | Language::Suomi
+            | Language::Svenska
+            | Language::Vlaams => None,
+        }
+    }
+
+    /// Whether the language has names for quarter tones
+    pub fn supports_quarter_tones(&self) -> bool {
+        self.quarter_tone_to_str(&QuarterToneNote::CSemiSharp)
+            .is_some()
+    }
+}
+
+impl Language {
+    /// Get the note for the given note name in this language
+    ///
+    /// Quarter tones are mapped to the note a quarter tone below, keeping the same letter (i.e.
+    /// a C semi-sharp becomes a C, a C semi-flat becomes a C flat). Some common alternative
+    /// spellings accepted by LilyPond are also recognized, such as `as` and `es` in
+    /// [Language::Nederlands], or `cflat` and `cx` in [Language::English].
+    ///
+    /// Returns `None` if the name is not a note name in this language.
+    pub fn str_to_pitch(&self, name: &str) -> Option<Note> {
+        for letter in 0..7 {
+            for alteration in -2..=2 {
+                let note = Note::from_letter_alteration(letter, alteration)
+                    .expect("Alteration is at most a double sharp or flat");
+                if self.note_to_str(&note) == name {
+                    return Some(note);
+                }
+            }
+            for quarter_tones in [-3, -1, 1, 3] {
+                let note = QuarterToneNote::from_letter_alteration(letter, quarter_tones)
+                    .expect("Alteration is an odd number of quarter tones");
+                if self.quarter_tone_to_str(&note) == Some(name) {
+                    return Note::from_letter_alteration(letter, (quarter_tones - 1) / 2);
+                }
+            }
+        }
+        self.alias_to_pitch(name)
+    }
+
+    /// Get the note for alternative note names which LilyPond accepts in this language
+    fn alias_to_pitch(&self, name: &str) -> Option<Note> {
+        match self {
+            Language::Nederlands => match name {
+                "as" => Some(Note::AFlat),
+                "ases" => Some(Note::AFlatFlat),
+                "es" => Some(Note::EFlat),
+                "eses" => Some(Note::EFlatFlat),
+                _ => None,
+            },
+            Language::Deutsch | Language::Suomi => match name {
+                "ases" => Some(Note::AFlatFlat),
+                "eeses" => Some(Note::EFlatFlat),
+                _ => None,
+            },
+            Language::English => {
+                let letter = "cdefgab".find(name.get(..1)?)? as u8;
+                let alteration = match name.get(1..)? {
+                    "flatflat" => -2,
+                    "flat" => -1,
+                    "sharp" => 1,
+                    "sharpsharp" | "x" => 2,
+                    _ => return None,
+                };
+                Note::from_letter_alteration(letter, alteration)
+            }
+            _ => None,
+        }
+    }
+}
+
 /// This enum is used to specify notes in the source code, but have them be
 /// converted to a language dependent string.
 ///
 /// See: <https://lilypond.org/doc/v2.25/Documentation/notation/writing-pitches#note-names-in-other-languages>
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Note {
     C,
+    CFlatFlat,
     CFlat,
     CSharp,
     CSharpSharp,
     D,
+    DFlatFlat,
     DFlat,
     DSharp,
+    DSharpSharp,
     E,
+    EFlatFlat,
     EFlat,
     ESharp,
+    ESharpSharp,
     F,
+    FFlatFlat,
     FFlat,
     FSharp,
     FSharpSharp,
     G,
+    GFlatFlat,
     GFlat,
     GSharp,
     GSharpSharp,
     A,
+    AFlatFlat,
     AFlat,
     ASharp,
+    ASharpSharp,
     B,
+    BFlatFlat,
     BFlat,
     BSharp,
+    BSharpSharp,
+}
+
+/// Pitch classes of the natural notes C, D, E, F, G, A and B
+pub const NATURAL_PITCH_CLASSES: [MidiNote; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+impl Note {
+    /// Get the note from its letter (C=0, D=1, ..., B=6) and its alteration in semitones
+    ///
+    /// Returns `None` if there is no such spelling, i.e. if the alteration is more than a double
+    /// sharp or a double flat.
+    pub fn from_letter_alteration(letter: u8, alteration: i8) -> Option<Note> {
+        match (letter, alteration) {
+            (0, -2) => Some(Note::CFlatFlat),
+            (0, -1) => Some(Note::CFlat),
+            (0, 0) => Some(Note::C),
+            (0, 1) => Some(Note::CSharp),
+            (0, 2) => Some(Note::CSharpSharp),
+            (1, -2) => Some(Note::DFlatFlat),
+            (1, -1) => Some(Note::DFlat),
+            (1, 0) => Some(Note::D),
+            (1, 1) => Some(Note::DSharp),
+            (1, 2) => Some(Note::DSharpSharp),
+            (2, -2) => Some(Note::EFlatFlat),
+            (2, -1) => Some(Note::EFlat),
+            (2, 0) => Some(Note::E),
+            (2, 1) => Some(Note::ESharp),
+            (2, 2) => Some(Note::ESharpSharp),
+            (3, -2) => Some(Note::FFlatFlat),
+            (3, -1) => Some(Note::FFlat),
+            (3, 0) => Some(Note::F),
+            (3, 1) => Some(Note::FSharp),
+            (3, 2) => Some(Note::FSharpSharp),
+            (4, -2) => Some(Note::GFlatFlat),
+            (4, -1) => Some(Note::GFlat),
+            (4, 0) => Some(Note::G),
+            (4, 1) => Some(Note::GSharp),
+            (4, 2) => Some(Note::GSharpSharp),
+            (5, -2) => Some(Note::AFlatFlat),
+            (5, -1) => Some(Note::AFlat),
+            (5, 0) => Some(Note::A),
+            (5, 1) => Some(Note::ASharp),
+            (5, 2) => Some(Note::ASharpSharp),
+            (6, -2) => Some(Note::BFlatFlat),
+            (6, -1) => Some(Note::BFlat),
+            (6, 0) => Some(Note::B),
+            (6, 1) => Some(Note::BSharp),
+            (6, 2) => Some(Note::BSharpSharp),
+            _ => None,
+        }
+    }
+
+    /// The letter of the note, with C=0, D=1, ..., B=6
+    pub fn letter(&self) -> u8 {
+        match self {
+            Note::CFlatFlat | Note::CFlat | Note::C | Note::CSharp | Note::CSharpSharp => 0,
+            Note::DFlatFlat | Note::DFlat | Note::D | Note::DSharp | Note::DSharpSharp => 1,
+            Note::EFlatFlat | Note::EFlat | Note::E | Note::ESharp | Note::ESharpSharp => 2,
+            Note::FFlatFlat | Note::FFlat | Note::F | Note::FSharp | Note::FSharpSharp => 3,
+            Note::GFlatFlat | Note::GFlat | Note::G | Note::GSharp | Note::GSharpSharp => 4,
+            Note::AFlatFlat | Note::AFlat | Note::A | Note::ASharp | Note::ASharpSharp => 5,
+            Note::BFlatFlat | Note::BFlat | Note::B | Note::BSharp | Note::BSharpSharp => 6,
+        }
+    }
+
+    /// The alteration of the note in semitones (negative for flats, positive for sharps)
+    pub fn alteration(&self) -> i8 {
+        match self {
+            Note::CFlatFlat
+            | Note::DFlatFlat
+            | Note::EFlatFlat
+            | Note::FFlatFlat
+            | Note::GFlatFlat
+            | Note::AFlatFlat
+            | Note::BFlatFlat => -2,
+            Note::CFlat
+            | Note::DFlat
+            | Note::EFlat
+            | Note::FFlat
+            | Note::GFlat
+            | Note::AFlat
+            | Note::BFlat => -1,
+            Note::C | Note::D | Note::E | Note::F | Note::G | Note::A | Note::B => 0,
+            Note::CSharp
+            | Note::DSharp
+            | Note::ESharp
+            | Note::FSharp
+            | Note::GSharp
+            | Note::ASharp
+            | Note::BSharp => 1,
+            Note::CSharpSharp
+            | Note::DSharpSharp
+            | Note::ESharpSharp
+            | Note::FSharpSharp
+            | Note::GSharpSharp
+            | Note::ASharpSharp
+            | Note::BSharpSharp => 2,
+        }
+    }
+
+    /// The pitch class of the note within an octave (0 to 11 inclusive)
+    pub fn pitch_class(&self) -> MidiNote {
+        (NATURAL_PITCH_CLASSES[self.letter() as usize] as i8 + self.alteration()).rem_euclid(12)
+            as MidiNote
+    }
+
+    /// Position of the note in the circle of fifths, relative to C (i.e. G=1, F=-1, B♭=-2)
+    pub fn fifths(&self) -> i8 {
+        const LETTER_FIFTHS: [i8; 7] = [0, 2, 4, -1, 1, 3, 5];
+        LETTER_FIFTHS[self.letter() as usize] + 7 * self.alteration()
+    }
+}
+
+/// Notes altered by a quarter tone or by three quarter tones, which lie in between the ones
+/// given by [Note]
+///
+/// See: <https://lilypond.org/doc/v2.25/Documentation/notation/writing-pitches#note-names-in-other-languages>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuarterToneNote {
+    CSesquiFlat,
+    CSemiFlat,
+    CSemiSharp,
+    CSesquiSharp,
+    DSesquiFlat,
+    DSemiFlat,
+    DSemiSharp,
+    DSesquiSharp,
+    ESesquiFlat,
+    ESemiFlat,
+    ESemiSharp,
+    ESesquiSharp,
+    FSesquiFlat,
+    FSemiFlat,
+    FSemiSharp,
+    FSesquiSharp,
+    GSesquiFlat,
+    GSemiFlat,
+    GSemiSharp,
+    GSesquiSharp,
+    ASesquiFlat,
+    ASemiFlat,
+    ASemiSharp,
+    ASesquiSharp,
+    BSesquiFlat,
+    BSemiFlat,
+    BSemiSharp,
+    BSesquiSharp,
+}
+
+impl QuarterToneNote {
+    /// Get the note from its letter (C=0, D=1, ..., B=6) and its alteration in quarter tones
+    ///
+    /// Returns `None` if the alteration is not an odd number of quarter tones between a
+    /// sesqui-flat and a sesqui-sharp.
+    pub fn from_letter_alteration(letter: u8, quarter_tones: i8) -> Option<QuarterToneNote> {
+        match (letter, quarter_tones) {
+            (0, -3) => Some(QuarterToneNote::CSesquiFlat),
+            (0, -1) => Some(QuarterToneNote::CSemiFlat),
+            (0, 1) => Some(QuarterToneNote::CSemiSharp),
+            (0, 3) => Some(QuarterToneNote::CSesquiSharp),
+            (1, -3) => Some(QuarterToneNote::DSesquiFlat),
+            (1, -1) => Some(QuarterToneNote::DSemiFlat),
+            (1, 1) => Some(QuarterToneNote::DSemiSharp),
+            (1, 3) => Some(QuarterToneNote::DSesquiSharp),
+            (2, -3) => Some(QuarterToneNote::ESesquiFlat),
+            (2, -1) => Some(QuarterToneNote::ESemiFlat),
+            (2, 1) => Some(QuarterToneNote::ESemiSharp),
+            (2, 3) => Some(QuarterToneNote::ESesquiSharp),
+            (3, -3) => Some(QuarterToneNote::FSesquiFlat),
+            (3, -1) => Some(QuarterToneNote::FSemiFlat),
+            (3, 1) => Some(QuarterToneNote::FSemiSharp),
+            (3, 3) => Some(QuarterToneNote::FSesquiSharp),
+            (4, -3) => Some(QuarterToneNote::GSesquiFlat),
+            (4, -1) => Some(QuarterToneNote::GSemiFlat),
+            (4, 1) => Some(QuarterToneNote::GSemiSharp),
+            (4, 3) => Some(QuarterToneNote::GSesquiSharp),
+            (5, -3) => Some(QuarterToneNote::ASesquiFlat),
+            (5, -1) => Some(QuarterToneNote::ASemiFlat),
+            (5, 1) => Some(QuarterToneNote::ASemiSharp),
+            (5, 3) => Some(QuarterToneNote::ASesquiSharp),
+            (6, -3) => Some(QuarterToneNote::BSesquiFlat),
+            (6, -1) => Some(QuarterToneNote::BSemiFlat),
+            (6, 1) => Some(QuarterToneNote::BSemiSharp),
+            (6, 3) => Some(QuarterToneNote::BSesquiSharp),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::MidiNote;
+
+use super::ModifierError;
+
+/// A control which adds a suffix to the notes while it is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ModifierControl {
+    /// A pedal or another controller, such as a foot switch, held while its value is above 0
+    Controller(u8),
+    /// A key (at concert pitch) reserved for the modifier, which never produces a note
+    Key(MidiNote),
+}
+
+impl FromStr for ModifierControl {
+    type Err = ModifierError;
+
+    /// Parse a control given as `cc<controller>` or `note<note>` (e.g. `cc67` or `note21`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |n: &str| n.parse::<u8>().ok().filter(|n| *n < 128);
+        match (s.strip_prefix("cc"), s.strip_prefix("note")) {
+            (Some(controller), _) => number(controller).map(ModifierControl::Controller),
+            (_, Some(note)) => number(note).map(ModifierControl::Key),
+            _ => None,
+        }
+        .ok_or_else(|| ModifierError::InvalidModifierControlString(s.into()))
+    }
+}
+
+impl Display for ModifierControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModifierControl::Controller(controller) => write!(f, "cc{controller}"),
+            ModifierControl::Key(note) => write!(f, "note{note}"),
+        }
+    }
+}
+
+/// Suffixes which held controls add to the next note or chord
+///
+/// A forced (`!`) or cautionary (`?`) accidental belongs to the pitch, and is written before an
+/// inline octave check (`cis'!=''`). All other suffixes are post-events such as `~`, `-.` or
+/// `\fermata`, which are written after the note or chord.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    forced_accidental: bool,
+    cautionary_accidental: bool,
+    post_events: String,
+}
+
+impl Modifiers {
+    /// Add the suffix of a control
+    pub fn add(&mut self, suffix: &str) {
+        match suffix {
+            "!" => self.forced_accidental = true,
+            "?" => self.cautionary_accidental = true,
+            _ => self.post_events.push_str(suffix),
+        }
+    }
+
+    /// The marks written directly after the pitch of each note
+    pub fn pitch_marks(&self) -> &'static str {
+        match (self.forced_accidental, self.cautionary_accidental) {
+            (false, false) => "",
+            (true, false) => "!",
+            (false, true) => "?",
+            (true, true) => "!?",
+        }
+    }
+
+    /// The post-events written after the note or chord
+    pub fn post_events(&self) -> &str {
+        &self.post_events
+    }
+
+    /// The marks for the pitches of the notes within a chord, whose post-events are written
+    /// after the chord instead
+    pub fn without_post_events(&self) -> Self {
+        Modifiers {
+            post_events: String::new(),
+            ..self.clone()
+        }
+    }
+}
@@ -4,8 +4,11 @@ use regex::Regex;
 
 use crate::MidiNote;
 
-use super::language::Note;
-use super::{LilyAccidental, LilyKeySignature, LilyParameters, LilypondNoteError};
+use super::language::{Note, QuarterToneNote};
+use super::{
+    Language, LilyAccidental, LilyKeySignature, LilyParameters, LilypondNoteError, Modifiers,
+    OctaveCheckStyle,
+};
 
 #[derive(Debug)]
 pub struct LilyNote<'a> {
@@ -17,52 +20,176 @@ pub struct LilyNote<'a> {
     note: MidiNote,
     /// absolute octave to include for octave check
     octave_check: Option<i8>,
+    /// pitch name to use for the octave check when it is written as an `\octaveCheck` command
+    /// instead of inline
+    octave_check_command: Option<&'static str>,
+    /// how the midi value is spelled, unless it was replaced by a custom alteration
+    spelling: Option<Note>,
+    /// the pitch (and its spelling, if known) which the next note in relative octave entry is
+    /// relative to
+    reference: (MidiNote, Option<Note>),
+    /// custom alteration wrapping the note, in which the placeholder is replaced by the note
+    template: Option<&'a str>,
+    /// string number to play the note on (`c\3`)
+    string: Option<u8>,
+    /// forced or cautionary accidental, written directly after the pitch (`cis'!`)
+    pitch_marks: &'static str,
+    /// post-events written after the note, such as `~` or `\fermata`
+    post_events: String,
 }
 
+/// Placeholder in custom alterations for the note which would have been generated otherwise
+pub const NOTE_PLACEHOLDER: &str = "{note}";
+
 #[derive(Debug)]
 pub struct LilyNoteRendered {
     /// The string representation of the note (without octave)
     note_name: &'static str,
+    /// How the midi note value is spelled
+    spelling: Note,
     /// The midi note value with the accidental removed (needed for calculating relative octave entry)
-    note_no_accidental: MidiNote,
+    ///
+    /// This may fall outside of the MIDI range, such as for a B sharp spelling MIDI note 0.
+    note_no_accidental: i16,
 }
 
 impl<'a> LilyNote<'a> {
     pub fn new(value: MidiNote, parameters: &'a LilyParameters) -> Self {
+        Self::new_spelled(value, None, parameters)
+    }
+
+    /// Create a note, optionally enforcing how it is spelled instead of deriving the spelling
+    /// from the key signature and accidentals
+    ///
+    /// Spelling overrides set in the parameters take precedence over the given spelling.
+    pub fn new_spelled(
+        value: MidiNote,
+        spelling: Option<&Note>,
+        parameters: &'a LilyParameters,
+    ) -> Self {
+        let note_rendered = match parameters.spelling_override(value).or(spelling) {
+            Some(spelling) => Self::render_spelled(value, spelling, &parameters.language),
+            None => Self::render(value, parameters),
+        };
+        Self::from_rendered(value, note_rendered, true, parameters)
+    }
+
+    /// Create a note which was bent by the given number of quarter tones
+    ///
+    /// An even number of quarter tones simply shifts the note by semitones. Otherwise, the note
+    /// in the direction of the bend keeps its spelling, and is altered by a quarter tone
+    /// (i.e. a C bent upwards becomes a C semi-sharp, a D bent downwards a D semi-flat).
+    /// Alterations are not applied to quarter tones.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the note would need a quarter tone, but the
+    /// language has no names for quarter tones, or if the bent note falls outside of the MIDI
+    /// range.
+    pub fn new_bent(
+        value: MidiNote,
+        quarter_tones: i16,
+        parameters: &'a LilyParameters,
+    ) -> Result<Self, LilypondNoteError> {
+        let bent = 2 * value as i16 + quarter_tones;
+        if !(0..=2 * 127).contains(&bent) {
+            return Err(LilypondNoteError::OutsideMidiRange(bent));
+        }
+        if bent % 2 == 0 {
+            return Ok(Self::new((bent / 2) as MidiNote, parameters));
+        }
+        if !parameters.language.supports_quarter_tones() {
+            return Err(LilypondNoteError::UnsupportedQuarterTone(
+                parameters.language.clone(),
+            ));
+        }
+        // alter the note closer to the one being played, or the other neighbour if needed
+        let direction: i16 = if quarter_tones > 0 { 1 } else { -1 };
+        for direction in [direction, -direction] {
+            let base = ((bent - direction) / 2) as MidiNote;
+            let spelling = Self::spell(base, parameters);
+            let alteration = 2 * spelling.alteration() + direction as i8;
+            if let Some(quarter_tone) =
+                QuarterToneNote::from_letter_alteration(spelling.letter(), alteration)
+            {
+                let note_rendered = LilyNoteRendered {
+                    note_name: parameters
+                        .language
+                        .quarter_tone_to_str(&quarter_tone)
+                        .expect("Language supports quarter tones"),
+                    spelling,
+                    note_no_accidental: base as i16 - spelling.alteration() as i16,
+                };
+                return Ok(Self::from_rendered(base, note_rendered, false, parameters));
+            }
+        }
+        Err(LilypondNoteError::UnsupportedQuarterTone(
+            parameters.language.clone(),
+        ))
+    }
+
+    /// Create a note from its rendered name, calculating octaves and applying alterations if
+    /// desired
+    fn from_rendered(
+        value: MidiNote,
+        note_rendered: LilyNoteRendered,
+        with_alterations: bool,
+        parameters: &'a LilyParameters,
+    ) -> Self {
         let LilyParameters {
-            alterations,
+            language,
             global_alterations,
             octave_entry,
+            fixed_reference,
             previous_absolute_note_reference,
+            previous_absolute_note_reference_spelling,
             octave_check_on_next_note,
             octave_check_notes,
+            octave_check_style,
             ..
         } = parameters;
-        let note_rendered = Self::render(value, parameters);
-        let absolute_octave = (value as i16 / 12) as i8 - 4;
+        // custom alterations which declare their base pitch (e.g. `bis@b`) are placed like notes
+        let based_alteration = match with_alterations {
+            true => Self::based_alteration(value, parameters),
+            false => None,
+        };
+        let note_no_accidental = match based_alteration {
+            Some((_, base, _)) => base as i16,
+            None => note_rendered.note_no_accidental,
+        };
+        // octave of the key being played, used for custom alterations
+        let key_octave = (value as i16 / 12) as i8 - 4;
+        // octave of the note without accidental (i.e. a B sharp belongs to the octave below its C)
+        let absolute_octave = note_no_accidental.div_euclid(12) as i8 - 4;
+        // octave marks in fixed octave entry are counted from the octave of the reference pitch
+        let fixed_octave = (*fixed_reference as i16 / 12) as i8 - 4;
         let mut octave = match octave_entry {
             super::OctaveEntry::Absolute => absolute_octave,
+            super::OctaveEntry::Fixed => absolute_octave - fixed_octave,
             super::OctaveEntry::Relative => match previous_absolute_note_reference {
                 Some(panr) => {
-                    let panr_rendered = Self::render(*panr, parameters);
+                    let panr_rendered = match previous_absolute_note_reference_spelling {
+                        Some(panr_spelling) => Self::render_spelled(*panr, panr_spelling, language),
+                        None => Self::render(*panr, parameters),
+                    };
                     let next_octave_distance = if ((
                         // The previous note is a B
-                        panr_rendered.note_no_accidental % 12 == 11
+                        panr_rendered.note_no_accidental.rem_euclid(12) == 11
                     ) && (
                         // The current note is an F
-                        note_rendered.note_no_accidental % 12 == 5
+                        note_no_accidental.rem_euclid(12) == 5
                     ) && (
                         // Only consider B to F, not F to B
-                        panr_rendered.note_no_accidental < note_rendered.note_no_accidental
+                        panr_rendered.note_no_accidental < note_no_accidental
                     )) || ((
                         // The previous note is an F
-                        panr_rendered.note_no_accidental % 12 == 5
+                        panr_rendered.note_no_accidental.rem_euclid(12) == 5
                     ) && (
                         // The current note is a B
-                        note_rendered.note_no_accidental % 12 == 11
+                        note_no_accidental.rem_euclid(12) == 11
                     ) && (
                         // Only consider F to B, not B to F
-                        note_rendered.note_no_accidental < panr_rendered.note_no_accidental
+                        note_no_accidental < panr_rendered.note_no_accidental
                     )) {
                         // Handle special tritone case between the following notes:
                         // C D E F G A B C D E F G A B C
@@ -72,8 +199,7 @@ impl<'a> LilyNote<'a> {
                         // absolute relative distance until an octave mark is needed
                         6
                     };
-                    let interval: i16 = (note_rendered.note_no_accidental as i16)
-                        - (panr_rendered.note_no_accidental as i16);
+                    let interval: i16 = note_no_accidental - panr_rendered.note_no_accidental;
                     (if interval > next_octave_distance {
                         // ceil division
                         (interval - next_octave_distance - 1) / 12 + 1
@@ -91,29 +217,102 @@ impl<'a> LilyNote<'a> {
         let mut octave_check = match *octave_check_on_next_note || *octave_check_notes {
             true => Some(absolute_octave),
             false => match octave_entry {
-                super::OctaveEntry::Absolute => None,
+                super::OctaveEntry::Absolute | super::OctaveEntry::Fixed => None,
                 super::OctaveEntry::Relative => match previous_absolute_note_reference {
                     Some(_) => None,
                     None => Some(absolute_octave),
                 },
             },
         };
+        if !with_alterations {
+            return LilyNote {
+                letter: note_rendered.note_name,
+                octave,
+                note: value,
+                octave_check,
+                octave_check_command: Self::octave_check_command(
+                    octave_check_style,
+                    Some(note_rendered.spelling),
+                    language,
+                ),
+                spelling: Some(note_rendered.spelling),
+                reference: (value, Some(note_rendered.spelling)),
+                template: None,
+                string: None,
+                pitch_marks: "",
+                post_events: String::new(),
+            };
+        }
+        // templates wrap the note instead of replacing it (e.g. `\harmonic {note}`)
+        if let Some((template, _)) =
+            Self::alteration(value, parameters).filter(|(text, _)| text.contains(NOTE_PLACEHOLDER))
+        {
+            return LilyNote {
+                letter: note_rendered.note_name,
+                octave,
+                note: value,
+                octave_check,
+                octave_check_command: Self::octave_check_command(
+                    octave_check_style,
+                    Some(note_rendered.spelling),
+                    language,
+                ),
+                spelling: Some(note_rendered.spelling),
+                reference: (value, Some(note_rendered.spelling)),
+                template: Some(template),
+                string: None,
+                pitch_marks: "",
+                post_events: String::new(),
+            };
+        }
+        if let Some((text, base, natural)) = based_alteration {
+            return LilyNote {
+                letter: text,
+                octave,
+                note: value,
+                octave_check,
+                octave_check_command: Self::octave_check_command(
+                    octave_check_style,
+                    Some(natural),
+                    language,
+                ),
+                spelling: None,
+                reference: (base, Some(natural)),
+                template: None,
+                string: None,
+                pitch_marks: "",
+                post_events: String::new(),
+            };
+        }
+        let spelling = match global_alterations.contains_key(&value)
+            || parameters.alteration(value).is_some()
+        {
+            true => None,
+            false => Some(note_rendered.spelling),
+        };
         LilyNote {
             letter: match global_alterations.get(&value) {
                 Some(text) => {
                     octave = 0; // we do not want octaves for global custom alterations
                     if let super::OctaveEntry::Relative = octave_entry {
                         // we cannot determine relative position here, add octave check
-                        octave_check = Some(absolute_octave);
+                        octave_check = Some(key_octave);
                     }
                     text
                 }
-                None => match alterations.get(&(value % 12)) {
+                None => match parameters.alteration(value) {
                     Some(text) => match octave_entry {
-                        super::OctaveEntry::Absolute => Self::adjust_ottavation(text, &mut octave),
+                        super::OctaveEntry::Absolute => {
+                            octave = key_octave;
+                            Self::adjust_ottavation(text, &mut octave)
+                        }
+                        super::OctaveEntry::Fixed => {
+                            octave = key_octave - fixed_octave;
+                            Self::adjust_ottavation(text, &mut octave)
+                        }
                         super::OctaveEntry::Relative => {
                             // we cannot easily determine relative position here, add octave check
-                            octave_check = Some(absolute_octave);
+                            octave_check = Some(key_octave);
                             Self::adjust_ottavation(
                                 text,
                                 octave_check
@@ -128,9 +327,100 @@ impl<'a> LilyNote<'a> {
             octave,
             note: value,
             octave_check,
+            octave_check_command: Self::octave_check_command(
+                octave_check_style,
+                spelling,
+                language,
+            ),
+            spelling,
+            reference: (value, spelling),
+            template: None,
+            string: None,
+            pitch_marks: "",
+            post_events: String::new(),
         }
     }
 
+    /// The pitch name for an `\octaveCheck` command, if octave checks are to be written as one
+    ///
+    /// Notes without a known spelling (i.e. replaced by a custom alteration) always use inline
+    /// octave checks, as the text cannot be used as a pitch.
+    fn octave_check_command(
+        style: &OctaveCheckStyle,
+        spelling: Option<Note>,
+        language: &Language,
+    ) -> Option<&'static str> {
+        match style {
+            OctaveCheckStyle::Inline => None,
+            OctaveCheckStyle::Command => spelling.map(|spelling| language.note_to_str(&spelling)),
+        }
+    }
+
+    /// Write the octave check inline (`c'=''`) even if it is configured as a command
+    ///
+    /// This is needed for notes within chords, where `\octaveCheck` cannot be used.
+    pub fn with_inline_octave_check(mut self) -> Self {
+        self.octave_check_command = None;
+        self
+    }
+
+    /// Write the note to be played on the given string (counted from the highest string), if any
+    pub fn with_string(mut self, string: Option<u8>) -> Self {
+        self.string = string;
+        self
+    }
+
+    /// Write the note with the suffixes of held modifier controls (see [Modifiers])
+    pub fn with_modifiers(mut self, modifiers: &Modifiers) -> Self {
+        self.pitch_marks = modifiers.pitch_marks();
+        self.post_events = modifiers.post_events().into();
+        self
+    }
+
+    /// Find the custom alteration for the given note, and whether it is a global one
+    fn alteration(value: MidiNote, parameters: &'a LilyParameters) -> Option<(&'a str, bool)> {
+        match parameters.global_alterations.get(&value) {
+            Some(text) => Some((text, true)),
+            None => parameters
+                .alteration(value)
+                .map(|text| (text.as_str(), false)),
+        }
+    }
+
+    /// Find the custom alteration for the given note if it declares which pitch it stands for,
+    /// such as `bis@b` for the key of a C
+    ///
+    /// The base pitch is the closest one to the given note with the declared letter (the lower
+    /// one for a tritone). For alterations within an octave, it is shifted by trailing `+` or `-`.
+    /// Returns the text to output, the base pitch, and the natural note at the base pitch.
+    fn based_alteration(
+        value: MidiNote,
+        parameters: &'a LilyParameters,
+    ) -> Option<(&'a str, MidiNote, Note)> {
+        let (text, global) = Self::alteration(value, parameters)
+            .filter(|(text, _)| !text.contains(NOTE_PLACEHOLDER))?;
+        let (text, base) = text.rsplit_once('@')?;
+        let letter = pitch_in_any_language(base, &parameters.language)?.letter();
+        let mut shift = 0;
+        let text = match global {
+            true => text,
+            false => Self::adjust_ottavation(text, &mut shift),
+        };
+        let natural = super::NATURAL_PITCH_CLASSES[letter as usize] as i16;
+        let distance = (value as i16 - natural).rem_euclid(12);
+        let base = value as i16 - distance
+            + match distance {
+                0..=6 => 0,
+                _ => 12,
+            }
+            + 12 * shift as i16;
+        Some((
+            text,
+            MidiNote::try_from(base).ok().filter(|base| *base < 128)?,
+            Note::from_letter_alteration(letter, 0)?,
+        ))
+    }
+
     /// Function to adjust the `octave` if there are trailing `+` or `-`
     ///
     /// # Panics
@@ -155,257 +445,131 @@ impl<'a> LilyNote<'a> {
     }
 
     /// Function to render the given midi note
+    fn render(note: MidiNote, parameters: &LilyParameters) -> LilyNoteRendered {
+        Self::render_spelled(note, &Self::spell(note, parameters), &parameters.language)
+    }
+
+    /// Function to render the given midi note using an explicit spelling
+    fn render_spelled(note: MidiNote, spelling: &Note, language: &Language) -> LilyNoteRendered {
+        LilyNoteRendered {
+            note_name: language.note_to_str(spelling),
+            spelling: *spelling,
+            note_no_accidental: note as i16 - spelling.alteration() as i16,
+        }
+    }
+
+    /// Function to determine how the given midi note is spelled according to the spelling
+    /// overrides, or otherwise the key signature and the accidentals for out-of-key notes
     ///
     /// # Panics
     ///
     /// The given midi note value is put through a modulo 12 to map each note in the scale to a
-    /// specific rendered note. The panic occurs if the value of the module operation falls outside
+    /// specific spelling. The panic occurs if the value of the module operation falls outside
     /// of 0..11 (inclusive), which will never occur.
-    fn render(note: MidiNote, parameters: &LilyParameters) -> LilyNoteRendered {
-        let LilyParameters {
-            key,
-            accidentals,
-            language,
-            ..
-        } = parameters;
+    pub fn spell(note: MidiNote, parameters: &LilyParameters) -> Note {
+        if let Some(spelling) = parameters.spelling_override(note) {
+            return *spelling;
+        }
+        let key = &parameters.written_key();
+        let accidentals = &parameters.accidentals;
         use LilyKeySignature::*;
         match note % 12 {
             0 => match key {
-                CSharpMajor | ASharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::BSharp),
-                    note_no_accidental: note - 1,
-                },
-                CSharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::BSharp),
-                    note_no_accidental: note - 1,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::C),
-                    note_no_accidental: note,
-                },
+                CSharpMajor | ASharpMinor => Note::BSharp,
+                CSharpMinor => Note::BSharp,
+                _ => Note::C,
             },
             1 => match key {
                 AFlatMajor | FMinor | DFlatMajor | BFlatMinor | GFlatMajor | EFlatMinor
-                | CFlatMajor | AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::DFlat),
-                    note_no_accidental: note + 1,
-                },
+                | CFlatMajor | AFlatMinor => Note::DFlat,
                 DMajor | BMinor | AMajor | FSharpMinor | EMajor | CSharpMinor | BMajor
                 | GSharpMinor | FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => {
-                    LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::CSharp),
-                        note_no_accidental: note - 1,
-                    }
+                    Note::CSharp
                 }
-                DMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::CSharp),
-                    note_no_accidental: note - 1,
-                },
+                DMinor => Note::CSharp,
                 _ => match accidentals {
-                    LilyAccidental::Sharps => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::CSharp),
-                        note_no_accidental: note - 1,
-                    },
-                    LilyAccidental::Flats => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::DFlat),
-                        note_no_accidental: note + 1,
-                    },
+                    LilyAccidental::Sharps => Note::CSharp,
+                    LilyAccidental::Flats => Note::DFlat,
                 },
             },
             2 => match key {
-                EFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::D),
-                    note_no_accidental: note,
-                },
-                DSharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::CSharpSharp),
-                    note_no_accidental: note - 2,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::D),
-                    note_no_accidental: note,
-                },
+                EFlatMinor => Note::D,
+                DSharpMinor => Note::CSharpSharp,
+                _ => Note::D,
             },
             3 => match key {
                 BFlatMajor | GMinor | EFlatMajor | CMinor | AFlatMajor | FMinor | DFlatMajor
-                | BFlatMinor | GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => {
-                    LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::EFlat),
-                        note_no_accidental: note + 1,
-                    }
-                }
+                | BFlatMinor | GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => Note::EFlat,
                 EMajor | CSharpMinor | BMajor | GSharpMinor | FSharpMajor | DSharpMinor
-                | CSharpMajor | ASharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::DSharp),
-                    note_no_accidental: note - 1,
-                },
-                EMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::DSharp),
-                    note_no_accidental: note - 1,
-                },
+                | CSharpMajor | ASharpMinor => Note::DSharp,
+                EMinor => Note::DSharp,
                 _ => match accidentals {
-                    LilyAccidental::Sharps => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::DSharp),
-                        note_no_accidental: note - 1,
-                    },
-                    LilyAccidental::Flats => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::EFlat),
-                        note_no_accidental: note + 1,
-                    },
+                    LilyAccidental::Sharps => Note::DSharp,
+                    LilyAccidental::Flats => Note::EFlat,
                 },
             },
             4 => match key {
-                CFlatMajor | AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::FFlat),
-                    note_no_accidental: note + 1,
-                },
-                FMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::E),
-                    note_no_accidental: note,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::E),
-                    note_no_accidental: note,
-                },
+                CFlatMajor | AFlatMinor => Note::FFlat,
+                FMinor => Note::E,
+                _ => Note::E,
             },
             5 => match key {
-                FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::ESharp),
-                    note_no_accidental: note - 1,
-                },
-                FSharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::ESharp),
-                    note_no_accidental: note - 1,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::F),
-                    note_no_accidental: note,
-                },
+                FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => Note::ESharp,
+                FSharpMinor => Note::ESharp,
+                _ => Note::F,
             },
             6 => match key {
                 DFlatMajor | BFlatMinor | GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => {
-                    LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::GFlat),
-                        note_no_accidental: note + 1,
-                    }
+                    Note::GFlat
                 }
                 GMajor | EMinor | DMajor | BMinor | AMajor | FSharpMinor | EMajor | CSharpMinor
                 | BMajor | GSharpMinor | FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => {
-                    LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::FSharp),
-                        note_no_accidental: note - 1,
-                    }
+                    Note::FSharp
                 }
-                GMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::FSharp),
-                    note_no_accidental: note - 1,
-                },
+                GMinor => Note::FSharp,
                 _ => match accidentals {
-                    LilyAccidental::Sharps => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::FSharp),
-                        note_no_accidental: note - 1,
-                    },
-                    LilyAccidental::Flats => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::GFlat),
-                        note_no_accidental: note + 1,
-                    },
+                    LilyAccidental::Sharps => Note::FSharp,
+                    LilyAccidental::Flats => Note::GFlat,
                 },
             },
             7 => match key {
-                AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::G),
-                    note_no_accidental: note,
-                },
-                GSharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::FSharpSharp),
-                    note_no_accidental: note - 2,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::G),
-                    note_no_accidental: note,
-                },
+                AFlatMinor => Note::G,
+                GSharpMinor => Note::FSharpSharp,
+                _ => Note::G,
             },
             8 => match key {
                 EFlatMajor | CMinor | AFlatMajor | FMinor | DFlatMajor | BFlatMinor
-                | GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::AFlat),
-                    note_no_accidental: note + 1,
-                },
+                | GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => Note::AFlat,
                 AMajor | FSharpMinor | EMajor | CSharpMinor | BMajor | GSharpMinor
-                | FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::GSharp),
-                    note_no_accidental: note - 1,
-                },
-                AMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::GSharp),
-                    note_no_accidental: note - 1,
-                },
+                | FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => Note::GSharp,
+                AMinor => Note::GSharp,
                 _ => match accidentals {
-                    LilyAccidental::Sharps => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::GSharp),
-                        note_no_accidental: note - 1,
-                    },
-                    LilyAccidental::Flats => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::AFlat),
-                        note_no_accidental: note + 1,
-                    },
+                    LilyAccidental::Sharps => Note::GSharp,
+                    LilyAccidental::Flats => Note::AFlat,
                 },
             },
             9 => match key {
-                BFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::A),
-                    note_no_accidental: note,
-                },
-                ASharpMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::GSharpSharp),
-                    note_no_accidental: note - 2,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::A),
-                    note_no_accidental: note,
-                },
+                BFlatMinor => Note::A,
+                ASharpMinor => Note::GSharpSharp,
+                _ => Note::A,
             },
             10 => match key {
                 FMajor | DMinor | BFlatMajor | GMinor | EFlatMajor | CMinor | AFlatMajor
                 | FMinor | DFlatMajor | BFlatMinor | GFlatMajor | EFlatMinor | CFlatMajor
-                | AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::BFlat),
-                    note_no_accidental: note + 1,
-                },
+                | AFlatMinor => Note::BFlat,
                 BMajor | GSharpMinor | FSharpMajor | DSharpMinor | CSharpMajor | ASharpMinor => {
-                    LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::ASharp),
-                        note_no_accidental: note - 1,
-                    }
+                    Note::ASharp
                 }
-                BMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::ASharp),
-                    note_no_accidental: note - 1,
-                },
+                BMinor => Note::ASharp,
                 _ => match accidentals {
-                    LilyAccidental::Sharps => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::ASharp),
-                        note_no_accidental: note - 1,
-                    },
-                    LilyAccidental::Flats => LilyNoteRendered {
-                        note_name: language.note_to_str(&Note::BFlat),
-                        note_no_accidental: note + 1,
-                    },
+                    LilyAccidental::Sharps => Note::ASharp,
+                    LilyAccidental::Flats => Note::BFlat,
                 },
             },
             11 => match key {
-                GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::CFlat),
-                    note_no_accidental: note + 1,
-                },
-                CMinor => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::B),
-                    note_no_accidental: note,
-                },
-                _ => LilyNoteRendered {
-                    note_name: language.note_to_str(&Note::B),
-                    note_no_accidental: note,
-                },
+                GFlatMajor | EFlatMinor | CFlatMajor | AFlatMinor => Note::CFlat,
+                CMinor => Note::B,
+                _ => Note::B,
             },
             _ => panic!("Note within octave"),
         }
@@ -415,45 +579,110 @@ impl<'a> LilyNote<'a> {
         &self.note
     }
 
-    pub fn from_lilypond_str(s: &'a str) -> Result<Self, LilypondNoteError> {
+    /// How the midi value of the note is spelled, unless it was replaced by a custom alteration
+    pub fn spelling(&self) -> Option<&Note> {
+        self.spelling.as_ref()
+    }
+
+    /// The pitch which the next note in relative octave entry is relative to, together with its
+    /// spelling if known
+    ///
+    /// This is the note itself, unless a custom alteration declared another base pitch.
+    pub fn reference(&self) -> (MidiNote, Option<Note>) {
+        self.reference
+    }
+
+    /// The given pitch in absolute octave entry (e.g. `cis''`), such as for the reference pitch
+    /// of a `\relative` block
+    ///
+    /// The pitch is spelled according to the parameters if no spelling is given.
+    pub fn absolute_pitch(
+        note: MidiNote,
+        spelling: Option<Note>,
+        parameters: &LilyParameters,
+    ) -> String {
+        let rendered = match spelling {
+            Some(spelling) => Self::render_spelled(note, &spelling, &parameters.language),
+            None => Self::render(note, parameters),
+        };
+        let octave = rendered.note_no_accidental.div_euclid(12) as i8 - 4;
+        format!("{}{}", rendered.note_name, octave_marks(octave))
+    }
+
+    /// The diatonic step of the note on a staff, counted from the C four octaves below middle C
+    /// (i.e. `c'` is step 28 and `d'` is step 29)
+    ///
+    /// Notes replaced by a custom alteration are placed by their base pitch.
+    pub fn staff_step(&self, parameters: &LilyParameters) -> i16 {
+        let (note, spelling) = self.reference;
+        let spelling = spelling.unwrap_or_else(|| Self::spell(note, parameters));
+        let natural = note as i16 - spelling.alteration() as i16;
+        (natural.div_euclid(12) - 1) * 7 + spelling.letter() as i16
+    }
+
+    /// Parse a LilyPond note in absolute octave entry, such as `fis'`, `bisis,` or `cih''`
+    ///
+    /// The note name is looked up in the given language first, and in all other languages
+    /// afterwards. An octave check (`c'=''`) determines the octave of the note. Forced and
+    /// cautionary accidentals (`!` and `?`) are accepted but have no effect. Quarter tones are
+    /// mapped to the note a quarter tone below (i.e. `cih'` becomes `c'`, `ceh'` becomes
+    /// `ces'`).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the string is not exactly one LilyPond note in any
+    /// of the supported languages, or if the note falls outside of the MIDI range.
+    pub fn from_lilypond_str(s: &'a str, language: &Language) -> Result<Self, LilypondNoteError> {
         let re_lilypond_note =
-            Regex::new(r"(?<note>[abcdefg](?:[ie]?s)*)(?<octave>[',]+)?").expect("Regex is valid");
-        match re_lilypond_note.captures(s) {
-            Some(caps) => {
-                let letter = caps.name("note").unwrap().as_str();
-                let octave = match caps.name("octave") {
-                    Some(o) => match o.as_str().chars().next().expect("Octave is not empty") {
-                        ',' => -(o.len() as i8),
-                        '\'' => o.len() as i8,
-                        _ => panic!("This case should not happen. Octave should be `'` or `,`"),
-                    },
-                    None => 0,
-                };
-                let note: MidiNote = (octave + 4) as u8 * 12
-                    + match letter {
-                        "c" | "bis" | "deses" => 0,
-                        "cis" | "bisis" | "des" => 1,
-                        "d" | "cisis" | "eeses" => 2,
-                        "dis" | "ees" | "feses" => 3,
-                        "e" | "disis" | "fes" => 4,
-                        "f" | "eis" | "geses" => 5,
-                        "fis" | "eisis" | "ges" => 6,
-                        "g" | "fisis" | "aeses" => 7,
-                        "gis" | "aes" => 8,
-                        "a" | "gisis" | "beses" => 9,
-                        "ais" | "bes" | "ceses" => 10,
-                        "b" | "aisis" | "ces" => 11,
-                        _ => panic!("Unrecognized note letter: {letter}"),
-                    };
-                Ok(LilyNote {
-                    letter,
-                    octave,
-                    note,
-                    octave_check: None,
-                })
-            }
-            None => Err(LilypondNoteError::InvalidNoteString(s.into())),
-        }
+            Regex::new(r"^(?<note>[^',=!?[:space:]]+)(?<octave>'+|,+)?[!?]?(?:=(?<check>'+|,*))?$")
+                .expect("Regex is valid");
+        let invalid = || LilypondNoteError::InvalidNoteString(s.into());
+        let caps = re_lilypond_note.captures(s).ok_or_else(invalid)?;
+        let letter = caps.name("note").expect("Note is always matched").as_str();
+        let count_octave = |marks: &str| match marks.chars().next() {
+            Some(',') => -(marks.len() as i8),
+            Some(_) => marks.len() as i8,
+            None => 0,
+        };
+        let octave = caps.name("octave").map_or(0, |o| count_octave(o.as_str()));
+        let octave_check = caps.name("check").map(|c| count_octave(c.as_str()));
+        let spelling = pitch_in_any_language(letter, language).ok_or_else(invalid)?;
+        let note: i16 = (octave_check.unwrap_or(octave) as i16 + 4) * 12
+            + super::NATURAL_PITCH_CLASSES[spelling.letter() as usize] as i16
+            + spelling.alteration() as i16;
+        let note = MidiNote::try_from(note)
+            .ok()
+            .filter(|n| *n < 128)
+            .ok_or_else(invalid)?;
+        Ok(LilyNote {
+            letter,
+            octave,
+            note,
+            octave_check,
+            octave_check_command: None,
+            spelling: Some(spelling),
+            reference: (note, Some(spelling)),
+            template: None,
+            string: None,
+            pitch_marks: "",
+            post_events: String::new(),
+        })
+    }
+}
+
+/// Look up the note name in the given language first, and in all other languages afterwards
+pub(super) fn pitch_in_any_language(name: &str, language: &Language) -> Option<Note> {
+    std::iter::once(language)
+        .chain(<Language as clap::ValueEnum>::value_variants())
+        .find_map(|language| language.str_to_pitch(name))
+}
+
+/// The octave marks (`'` or `,`) for the given number of octaves above or below the reference
+fn octave_marks(octave: i8) -> String {
+    match octave.cmp(&0) {
+        std::cmp::Ordering::Less => ",".repeat(octave.unsigned_abs() as usize),
+        std::cmp::Ordering::Equal => "".into(),
+        std::cmp::Ordering::Greater => "'".repeat(octave as usize),
     }
 }
 
@@ -463,23 +692,36 @@ impl<'a> From<&LilyNote<'a>> for String {
             letter,
             octave,
             octave_check,
+            octave_check_command,
+            template,
+            string,
+            pitch_marks,
+            post_events,
             ..
         } = value;
-        let octave = match octave.cmp(&0) {
-            std::cmp::Ordering::Less => ",".repeat(octave.unsigned_abs() as usize),
-            std::cmp::Ordering::Equal => "".into(),
-            std::cmp::Ordering::Greater => "'".repeat(*octave as usize),
+        let note = match (octave_check, octave_check_command) {
+            (Some(check), None) => format!(
+                "{}{}{}={}",
+                letter,
+                octave_marks(*octave),
+                pitch_marks,
+                octave_marks(*check)
+            ),
+            _ => format!("{}{}{}", letter, octave_marks(*octave), pitch_marks),
         };
-        match octave_check {
-            Some(check) => {
-                let octave_check = match check.cmp(&0) {
-                    std::cmp::Ordering::Less => ",".repeat(check.unsigned_abs() as usize),
-                    std::cmp::Ordering::Equal => "".into(),
-                    std::cmp::Ordering::Greater => "'".repeat(*check as usize),
-                };
-                format!("{}{}={}", letter, octave, octave_check)
+        let note = match template {
+            Some(template) => template.replace(NOTE_PLACEHOLDER, &note),
+            None => note,
+        };
+        let note = match string {
+            Some(string) => format!("{}\\{}{}", note, string, post_events),
+            None => format!("{}{}", note, post_events),
+        };
+        match (octave_check, octave_check_command) {
+            (Some(check), Some(pitch)) => {
+                format!("{} \\octaveCheck {}{}", note, pitch, octave_marks(*check))
             }
-            None => format!("{}{}", letter, octave),
+            _ => note,
         }
     }
 }
@@ -1,4 +1,4 @@
-use super::OctaveEntryError;
+use super::{OctaveCheckStyleError, OctaveEntryError};
 
 make_lily_str_map!(
     /// The octave entry mode to use for note generation
@@ -6,4 +6,18 @@ make_lily_str_map!(
     OctaveEntryError::InvalidOctaveEntryString;
     Absolute, "absolute", "a";
     Relative, "relative", "r";
+    /// Octave marks are counted from the octave of a fixed reference pitch, as in `\fixed c' { ... }`
+    Fixed, "fixed", "f";
+);
+
+make_lily_str_map!(
+    /// How octave checks are written
+    #[derive(Default)]
+    OctaveCheckStyle;
+    OctaveCheckStyleError::InvalidOctaveCheckStyleString;
+    /// As part of the note, as in `c'=''`
+    #[default]
+    Inline, "inline", "i";
+    /// As a separate command after the note, as in `c' \octaveCheck c''`
+    Command, "command", "c";
 );
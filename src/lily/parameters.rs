@@ -1,14 +1,21 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 use getset::{Getters, Setters};
 
-use crate::{InputMode, MidiNote};
+use crate::{output::OutputTarget, InputMode, MidiNote, OutputMode};
 
-use super::{Language, LilyAccidental, LilyKeySignature, LilyNote, LilypondNoteError, OctaveEntry};
+use super::{
+    ChordNameError, ChordNamePreference, Clef, ClefError, Hand, HandSplit, HandSplitError,
+    HandState, Instrument, KeyswitchAction, KeyswitchError, Language, LilyAccidental, LilyContext,
+    LilyKeySignature, LilyNote, LilypondContextError, LilypondNoteError, ModifierControl,
+    ModifierError, Modifiers, Note, OctaveCheckStyle, OctaveEntry, PitchBendResolution, Tuning,
+    TuningError,
+};
 
 type Alteration = HashMap<MidiNote, String>;
+type Spelling = HashMap<MidiNote, Note>;
 
-#[derive(Debug, Getters, Setters)]
+#[derive(Debug, Clone, Getters, Setters)]
 pub struct LilyParameters {
     #[getset(get = "pub", set = "pub")]
     pub(super) key: LilyKeySignature,
@@ -17,27 +24,163 @@ pub struct LilyParameters {
     #[getset(get = "pub", set = "pub")]
     pub(super) mode: InputMode,
     #[getset(get = "pub", set = "pub")]
+    pub(super) output_mode: OutputMode,
+    #[getset(get = "pub", set = "pub")]
     pub(super) language: Language,
     #[getset(get = "pub", set = "pub")]
     pub(super) octave_entry: OctaveEntry,
+    /// the pitch sounding when a `c'` is written for a transposing instrument
+    #[getset(get = "pub")]
+    pub(super) transposition: MidiNote,
+    /// how the transposition is spelled, which determines the written key
+    #[getset(get = "pub")]
+    pub(super) transposition_spelling: Note,
+    /// whether to output a `\transposition` command when the transposition changes
+    #[getset(get = "pub", set = "pub")]
+    pub(super) transposition_command: bool,
+    /// the MIDI channel (1-16) dedicated to drums, whose notes are only used for drum output
+    /// (and the only ones used for it), or `None` to use all channels for any output
+    #[getset(get = "pub", set = "pub")]
+    pub(super) drum_channel: Option<u8>,
+    /// drum names overriding the General MIDI percussion key map
+    #[getset(get = "pub")]
+    pub(super) drum_names: HashMap<MidiNote, String>,
+    /// actions of the keys (at concert pitch) reserved as command keys, which never produce notes
+    #[getset(get = "pub")]
+    pub(super) keyswitches: HashMap<MidiNote, KeyswitchAction>,
+    /// suffixes which controls add to the notes while they are held (e.g. `!` or `\fermata`)
+    #[getset(get = "pub")]
+    pub(super) modifiers: BTreeMap<ModifierControl, String>,
+    /// modifier controls being held
+    pub(super) held_modifiers: BTreeSet<ModifierControl>,
+    /// modifier controls pressed since the last note or chord, which still apply to it if they
+    /// were released before it was output
+    #[getset(get = "pub", set = "pub")]
+    pub(super) pending_modifiers: BTreeSet<ModifierControl>,
+    /// where output which was taken back by an undo is announced
+    #[getset(get = "pub", set = "pub")]
+    pub(super) undo_output: OutputTarget,
+    /// where state which editors may show in a status line, such as the octave shift, is
+    /// reported, or `None` to not report it
+    #[getset(get = "pub", set = "pub")]
+    pub(super) status_output: Option<OutputTarget>,
+    /// where the figures go when outputting a figured bass
+    #[getset(get = "pub", set = "pub")]
+    pub(super) figures_output: OutputTarget,
+    /// criteria for choosing between several interpretations of a chord when naming it, in
+    /// order
+    #[getset(get = "pub")]
+    pub(super) chord_name_preferences: Vec<ChordNamePreference>,
+    /// pitches of the open strings (from the lowest string to the highest) to assign string
+    /// numbers to notes for, or `None` to not write string numbers
+    #[getset(get = "pub")]
+    pub(super) strings: Option<Vec<MidiNote>>,
+    /// lowest fret fretted by the previous notes, near which following notes are played
+    #[getset(get = "pub", set = "pub")]
+    pub(super) hand_position: Option<u8>,
+    /// how notes are split between the hands of a piano part, or `None` to not split them
+    #[getset(get = "pub")]
+    pub(super) hand_split: Option<HandSplit>,
+    /// the hand whose notes are currently output, whose state is held by the parameters
+    #[getset(get = "pub")]
+    pub(super) hand: Hand,
+    /// the state of the other hand
+    pub(super) other_hand: HandState,
+    /// centers of the notes the right and the left hand played last
+    pub(super) hand_centers: [Option<MidiNote>; 2],
+    /// where the notes of the right hand go when splitting notes between the hands
+    #[getset(get = "pub", set = "pub")]
+    pub(super) right_hand_output: OutputTarget,
+    /// where the notes of the left hand go when splitting notes between the hands
+    #[getset(get = "pub", set = "pub")]
+    pub(super) left_hand_output: OutputTarget,
+    /// lowest and highest notes (inclusive, at concert pitch) outside of which notes are warned
+    /// about
+    #[getset(get = "pub", set = "pub")]
+    pub(super) range: Option<(MidiNote, MidiNote)>,
+    /// the clef of the staff, which determines the number of ledger lines of a note
+    #[getset(get = "pub", set = "pub")]
+    pub(super) clef: Clef,
+    /// whether to switch between the allowed `clefs` depending on the register of the notes
+    #[getset(get = "pub", set = "pub")]
+    pub(super) auto_clef: bool,
+    /// the clefs which automatic clef switching may choose from
+    #[getset(get = "pub")]
+    pub(super) clefs: Vec<Clef>,
+    /// number of ledger lines a note may need before another clef is considered
+    #[getset(get = "pub", set = "pub")]
+    pub(super) clef_ledger_lines: u8,
+    /// number of consecutive notes which need to exceed `clef_ledger_lines` before the clef is
+    /// switched
+    #[getset(get = "pub", set = "pub")]
+    pub(super) clef_notes: u8,
+    /// number of ledger lines beyond which an `\ottava` is inserted, or `None` to never do so
+    #[getset(get = "pub", set = "pub")]
+    pub(super) ottava_ledger_lines: Option<u8>,
+    /// the reference pitch for fixed octave entry, of which only the octave is relevant
+    #[getset(get = "pub", set = "pub")]
+    pub(super) fixed_reference: MidiNote,
     /// control adding of octave check on the next generated note
     #[getset(get = "pub", set = "pub")]
     pub(super) octave_check_on_next_note: bool,
     /// control adding of octave checks on all generated notes
     #[getset(get = "pub", set = "pub")]
     pub(super) octave_check_notes: bool,
+    /// in relative octave entry, add an octave check on every n-th note (or chord), or never if 0
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_check_every: u8,
+    /// in relative octave entry, add an octave check on notes reached by a leap of more than an
+    /// octave
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_check_leaps: bool,
+    /// in relative octave entry, add an octave check on the first note of each chord
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_check_chords: bool,
+    /// in relative octave entry, add an octave check on the note (or chord) following a `q`
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_check_after_q: bool,
+    /// whether octave checks are written inline or as `\octaveCheck` commands
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_check_style: OctaveCheckStyle,
+    /// number of notes (or chords) generated since the last octave check
+    #[getset(get = "pub", set = "pub")]
+    pub(super) notes_since_octave_check: u8,
     /// custom alterations within an octave (0-11)
     #[getset(get = "pub")]
     pub(super) alterations: Alteration,
     /// custom alterations over all notes
     #[getset(get = "pub", set = "pub")]
     pub(super) global_alterations: Alteration,
+    /// named sets of custom alterations within an octave (0-11)
+    #[getset(get = "pub")]
+    pub(super) alteration_presets: HashMap<String, Alteration>,
+    /// the alteration preset in use, which is overridden by `alterations`
+    #[getset(get = "pub")]
+    pub(super) alteration_preset: Option<String>,
+    /// how notes within an octave (0-11) are spelled, regardless of the key signature
+    #[getset(get = "pub")]
+    pub(super) spellings: Spelling,
+    /// how specific notes are spelled, further overriding `spellings`
+    #[getset(get = "pub")]
+    pub(super) global_spellings: Spelling,
+    /// how finely pitch bends are taken into account
+    #[getset(get = "pub", set = "pub")]
+    pub(super) pitch_bend_resolution: PitchBendResolution,
+    /// number of semitones covered by a full pitch bend, as configured on the device
+    #[getset(get = "pub", set = "pub")]
+    pub(super) pitch_bend_range: u8,
+    /// number of octaves by which incoming notes are shifted, to reach the full range on a
+    /// small keyboard
+    #[getset(get = "pub", set = "pub")]
+    pub(super) octave_shift: i8,
     /// manually set the previous chord for generating a 'q' shorthand
     #[getset(set = "pub")]
     pub(super) previous_chord: Option<BTreeSet<MidiNote>>,
     /// the previous note in absolute pitch used to calculate the next note in relative pitch
-    #[getset(set = "pub")]
     pub(super) previous_absolute_note_reference: Option<MidiNote>,
+    /// how the previous absolute note reference was spelled, if it differs from the key signature
+    #[getset(get = "pub", set = "pub")]
+    pub(super) previous_absolute_note_reference_spelling: Option<Note>,
 }
 
 impl LilyParameters {
@@ -62,17 +205,82 @@ impl LilyParameters {
             key,
             accidentals,
             mode,
+            output_mode: OutputMode::default(),
             language,
             octave_entry,
+            transposition: 60,
+            transposition_spelling: Note::C,
+            transposition_command: false,
+            drum_channel: Some(10),
+            drum_names: HashMap::new(),
+            keyswitches: HashMap::new(),
+            modifiers: BTreeMap::new(),
+            held_modifiers: BTreeSet::new(),
+            pending_modifiers: BTreeSet::new(),
+            undo_output: OutputTarget::Tagged("undo".into()),
+            status_output: None,
+            figures_output: OutputTarget::Tagged("figures".into()),
+            chord_name_preferences: vec![
+                ChordNamePreference::Bass,
+                ChordNamePreference::Thirds,
+                ChordNamePreference::Simple,
+            ],
+            strings: None,
+            hand_position: None,
+            hand_split: None,
+            hand: Hand::Right,
+            other_hand: HandState::default(),
+            hand_centers: [None, None],
+            right_hand_output: OutputTarget::Tagged("rh".into()),
+            left_hand_output: OutputTarget::Tagged("lh".into()),
+            range: None,
+            clef: Clef::default(),
+            auto_clef: false,
+            clefs: vec![Clef::Bass, Clef::Treble],
+            clef_ledger_lines: 2,
+            clef_notes: 3,
+            ottava_ledger_lines: None,
+            fixed_reference: 60,
             octave_check_on_next_note,
             octave_check_notes,
+            octave_check_every: 0,
+            octave_check_leaps: false,
+            octave_check_chords: false,
+            octave_check_after_q: false,
+            octave_check_style: OctaveCheckStyle::default(),
+            notes_since_octave_check: 0,
             alterations,
             global_alterations,
+            alteration_presets: HashMap::new(),
+            alteration_preset: None,
+            spellings: HashMap::new(),
+            global_spellings: HashMap::new(),
+            pitch_bend_resolution: PitchBendResolution::default(),
+            pitch_bend_range: 2,
+            octave_shift: 0,
             previous_chord: None,
             previous_absolute_note_reference: None,
+            previous_absolute_note_reference_spelling: None,
         })
     }
 
+    /// Parameters in C major with sharps and absolute octave entry, for tests
+    #[cfg(test)]
+    pub(crate) fn for_tests() -> Self {
+        Self::new(
+            LilyKeySignature::CMajor,
+            LilyAccidental::Sharps,
+            InputMode::Single,
+            Language::default(),
+            OctaveEntry::Absolute,
+            false,
+            false,
+            Alteration::new(),
+            Alteration::new(),
+        )
+        .expect("Parameters without alterations are valid")
+    }
+
     pub fn set_alterations(
         &mut self,
         alterations: Alteration,
@@ -109,12 +317,140 @@ impl LilyParameters {
             Err(LilypondNoteError::OutsideOctave(*note))
         }
     }
+    /// The custom alteration for the given note, taken from `alterations` or otherwise from the
+    /// alteration preset in use
+    pub fn alteration(&self, note: MidiNote) -> Option<&String> {
+        self.alterations.get(&(note % 12)).or_else(|| {
+            self.alteration_preset
+                .as_ref()
+                .and_then(|name| self.alteration_presets.get(name))
+                .and_then(|preset| preset.get(&(note % 12)))
+        })
+    }
+    /// Define (or redefine) a named set of alterations within an octave
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if one of the notes is outside of an octave, in which
+    /// case the preset is not defined.
+    pub fn define_alteration_preset(
+        &mut self,
+        name: String,
+        alterations: Alteration,
+    ) -> Result<&mut Self, LilypondNoteError> {
+        for alt in &alterations {
+            Self::verify_alteration(alt.0)?;
+        }
+        self.alteration_presets.insert(name, alterations);
+        Ok(self)
+    }
+    /// Select the alteration preset to use, or none at all
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no preset with the given name was defined.
+    pub fn set_alteration_preset(
+        &mut self,
+        name: Option<String>,
+    ) -> Result<&mut Self, LilyParametersError> {
+        if let Some(name) = &name {
+            if !self.alteration_presets.contains_key(name) {
+                return Err(LilyParametersError::UnknownAlterationPreset(name.clone()));
+            }
+        }
+        self.alteration_preset = name;
+        Ok(self)
+    }
     pub fn add_global_alteration(&mut self, note: MidiNote, value: String) {
         self.global_alterations.insert(note, value);
     }
     pub fn clear_global_alterations(&mut self) {
         self.set_global_alterations(HashMap::new());
     }
+    /// Spell all notes of the given pitch class (0-11) with the given note name, which may be in
+    /// any language
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pitch class is outside of an octave, if the
+    /// note name is not recognized, or if it does not spell the pitch class.
+    pub fn add_spelling(
+        &mut self,
+        note: MidiNote,
+        spelling: &str,
+    ) -> Result<&mut Self, LilypondNoteError> {
+        Self::verify_alteration(&note)?;
+        let spelling = Self::verify_spelling(note, spelling, &self.language)?;
+        self.spellings.insert(note, spelling);
+        Ok(self)
+    }
+    pub fn clear_spellings(&mut self) {
+        self.spellings.clear();
+    }
+    /// Spell the given MIDI note with the given note name, which may be in any language
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the note name is not recognized, or if it does
+    /// not spell the MIDI note.
+    pub fn add_global_spelling(
+        &mut self,
+        note: MidiNote,
+        spelling: &str,
+    ) -> Result<&mut Self, LilypondNoteError> {
+        let spelling = Self::verify_spelling(note, spelling, &self.language)?;
+        self.global_spellings.insert(note, spelling);
+        Ok(self)
+    }
+    pub fn clear_global_spellings(&mut self) {
+        self.global_spellings.clear();
+    }
+    /// Parse the note name and verify that it spells the given note
+    fn verify_spelling(
+        note: MidiNote,
+        spelling: &str,
+        language: &Language,
+    ) -> Result<Note, LilypondNoteError> {
+        let spelling = super::note::pitch_in_any_language(spelling, language)
+            .ok_or_else(|| LilypondNoteError::InvalidNoteString(spelling.into()))?;
+        match spelling.pitch_class() == note % 12 {
+            true => Ok(spelling),
+            false => Err(LilypondNoteError::InvalidSpelling(note, spelling)),
+        }
+    }
+    /// How the given note is spelled according to the spelling overrides, if at all
+    pub fn spelling_override(&self, note: MidiNote) -> Option<&Note> {
+        self.global_spellings
+            .get(&note)
+            .or_else(|| self.spellings.get(&(note % 12)))
+    }
+    /// Request an octave check on the next note if the octave check policy calls for one
+    ///
+    /// This is to be called before each note, chord or `q` is generated, with the (lowest) note
+    /// and whether it starts a chord. The policy only applies to relative octave entry.
+    pub fn apply_octave_check_policy(&mut self, note: MidiNote, chord: bool) -> &mut Self {
+        if self.octave_entry != OctaveEntry::Relative {
+            return self;
+        }
+        self.notes_since_octave_check = self.notes_since_octave_check.saturating_add(1);
+        let leap = self
+            .previous_absolute_note_reference
+            .is_some_and(|panr| note.abs_diff(panr) > 12);
+        if (self.octave_check_every > 0 && self.notes_since_octave_check >= self.octave_check_every)
+            || (self.octave_check_leaps && leap)
+            || (self.octave_check_chords && chord)
+        {
+            self.octave_check_on_next_note = true;
+        }
+        // notes without a previous note to be relative to always have an octave check
+        if self.octave_check_on_next_note
+            || self.octave_check_notes
+            || self.previous_absolute_note_reference.is_none()
+        {
+            self.notes_since_octave_check = 0;
+        }
+        self
+    }
     pub fn take_previous_chord(&mut self) -> Option<BTreeSet<MidiNote>> {
         self.previous_chord.take()
     }
@@ -127,25 +463,462 @@ impl LilyParameters {
     ) -> Result<&mut Self, LilypondNoteError> {
         let mut chord = BTreeSet::new();
         for note in previous_chord.into_iter() {
-            chord.insert(*LilyNote::from_lilypond_str(note.as_str())?.note());
+            chord.insert(*LilyNote::from_lilypond_str(note.as_str(), &self.language)?.note());
         }
         self.previous_chord = Some(chord);
         Ok(self)
     }
+    pub fn set_fixed_reference_lilypond_str(
+        &mut self,
+        fixed_reference: &str,
+    ) -> Result<&mut Self, LilypondNoteError> {
+        let note = LilyNote::from_lilypond_str(fixed_reference, &self.language)?;
+        // only keep the octave of the letter, as a B sharp belongs to the octave below its C
+        let alteration = note.spelling().map_or(0, |spelling| spelling.alteration());
+        self.fixed_reference = (*note.note() as i16 - alteration as i16) as MidiNote;
+        Ok(self)
+    }
+    /// Set the transposition of the instrument as the absolute LilyPond note sounding when a
+    /// `c'` is written (e.g. `bes` for a B♭ clarinet, `f` for a horn in F)
+    pub fn set_transposition_lilypond_str(
+        &mut self,
+        transposition: &str,
+    ) -> Result<&mut Self, LilypondNoteError> {
+        let note = LilyNote::from_lilypond_str(transposition, &self.language)?;
+        self.transposition = *note.note();
+        self.transposition_spelling = *note.spelling().expect("Parsed notes are spelled");
+        Ok(self)
+    }
+    /// The transposition as an absolute LilyPond note in the current language, as used for the
+    /// `\transposition` command
+    pub fn transposition_lilypond_str(&self) -> String {
+        let natural = self.transposition as i16 - self.transposition_spelling.alteration() as i16;
+        let octave = natural.div_euclid(12) - 4;
+        format!(
+            "{}{}",
+            self.language.note_to_str(&self.transposition_spelling),
+            match octave.cmp(&0) {
+                std::cmp::Ordering::Less => ",".repeat(octave.unsigned_abs() as usize),
+                std::cmp::Ordering::Equal => "".into(),
+                std::cmp::Ordering::Greater => "'".repeat(octave as usize),
+            }
+        )
+    }
+    /// The note for the given key on the keyboard, shifted by the octave shift
+    ///
+    /// Returns `None` if the shifted note falls outside of the MIDI range.
+    pub fn shift_octave(&self, note: MidiNote) -> Option<MidiNote> {
+        MidiNote::try_from(note as i16 + 12 * self.octave_shift as i16)
+            .ok()
+            .filter(|note| *note < 128)
+    }
+    /// Report the octave shift on the status output (e.g. `octave-shift=-1`), if there is one
+    pub fn report_octave_shift(&self) {
+        if let Some(target) = &self.status_output {
+            target.write(&format!("octave-shift={}", self.octave_shift));
+        }
+    }
+    /// The written note for the given note sounding at concert pitch
+    ///
+    /// Returns `None` if the written note falls outside of the MIDI range.
+    pub fn transpose(&self, note: MidiNote) -> Option<MidiNote> {
+        MidiNote::try_from(note as i16 + 60 - self.transposition as i16)
+            .ok()
+            .filter(|note| *note < 128)
+    }
+    /// The key signature in which notes are written, which is the key transposed for the
+    /// instrument's transposition
+    pub fn written_key(&self) -> LilyKeySignature {
+        self.key.transpose(-self.transposition_spelling.fifths())
+    }
+    /// Name the given note in drum output, overriding the General MIDI percussion key map
+    pub fn add_drum_name(&mut self, note: MidiNote, name: String) {
+        self.drum_names.insert(note, name);
+    }
+    pub fn clear_drum_names(&mut self) {
+        self.drum_names.clear();
+    }
+    /// The `\drummode` name of the given note, if it has one
+    pub fn drum_name(&self, note: MidiNote) -> Option<&str> {
+        self.drum_names
+            .get(&note)
+            .map(String::as_str)
+            .or_else(|| super::gm_drum_name(note))
+    }
+    /// Reserve the given key as a command key triggering the given action (see
+    /// [KeyswitchAction])
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the action is not recognized.
+    pub fn add_keyswitch(&mut self, note: MidiNote, action: &str) -> Result<(), KeyswitchError> {
+        self.keyswitches
+            .insert(note, KeyswitchAction::try_from(action)?);
+        Ok(())
+    }
+    pub fn clear_keyswitches(&mut self) {
+        self.keyswitches.clear();
+    }
+    /// The action of the given key, if it is reserved as a command key
+    pub fn keyswitch(&self, note: MidiNote) -> Option<&KeyswitchAction> {
+        self.keyswitches.get(&note)
+    }
+    /// Make the given control (`cc<controller>` or `note<note>`) add the given suffix to the
+    /// notes while it is held
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the control is not valid.
+    pub fn add_modifier(&mut self, control: &str, suffix: &str) -> Result<(), ModifierError> {
+        self.modifiers.insert(control.parse()?, suffix.into());
+        Ok(())
+    }
+    pub fn clear_modifiers(&mut self) {
+        self.modifiers.clear();
+        self.held_modifiers.clear();
+        self.pending_modifiers.clear();
+    }
+    /// Press or release a modifier control, returning whether the control is a modifier
+    pub fn hold_modifier(&mut self, control: ModifierControl, held: bool) -> bool {
+        if !self.modifiers.contains_key(&control) {
+            return false;
+        }
+        match held {
+            true => {
+                self.held_modifiers.insert(control);
+                self.pending_modifiers.insert(control);
+            }
+            false => {
+                self.held_modifiers.remove(&control);
+            }
+        }
+        true
+    }
+    /// The suffixes for the next note or chord, from the modifier controls which are held or
+    /// were pressed since the previous one
+    pub fn take_modifiers(&mut self) -> Modifiers {
+        let mut modifiers = Modifiers::default();
+        for control in self.held_modifiers.union(&self.pending_modifiers) {
+            modifiers.add(&self.modifiers[control]);
+        }
+        self.pending_modifiers.clear();
+        modifiers
+    }
+    /// Set the criteria for choosing between several interpretations of a chord when naming it,
+    /// given as a comma separated list in order (e.g. `bass,thirds`)
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if one of the criteria is not recognized, in which
+    /// case the preferences are not changed.
+    pub fn set_chord_name_preferences_str(
+        &mut self,
+        preferences: &str,
+    ) -> Result<&mut Self, ChordNameError> {
+        self.chord_name_preferences = preferences
+            .split(',')
+            .map(ChordNamePreference::try_from)
+            .collect::<Result<Vec<ChordNamePreference>, ChordNameError>>(
+        )?;
+        Ok(self)
+    }
+    /// The `\chordmode` name of the given notes (see [super::chord_name])
+    pub fn chord_name(&self, notes: &BTreeSet<MidiNote>) -> Option<String> {
+        super::chord_name(notes, &self.chord_name_preferences, self)
+    }
+    /// Set the tuning of the strings to assign string numbers for
+    ///
+    /// The tuning is either one of the [Tuning]s, a colon separated list of the open strings as
+    /// LilyPond notes in absolute octave entry from the lowest string to the highest (e.g.
+    /// `e,:a,:d:g:b:e'`), or `none` to not write string numbers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the tuning is not recognized, or one of the open
+    /// strings is not a valid note, in which case the tuning is not changed.
+    pub fn set_tuning_str(&mut self, tuning: &str) -> Result<&mut Self, TuningError> {
+        let invalid = || TuningError::InvalidTuningString(tuning.into());
+        self.strings = match tuning {
+            "none" => None,
+            _ => match Tuning::try_from(tuning) {
+                Ok(tuning) => Some(tuning.strings()),
+                Err(_) => Some(
+                    tuning
+                        .split(':')
+                        .map(|string| {
+                            LilyNote::from_lilypond_str(string, &self.language)
+                                .map(|note| *note.note())
+                                .map_err(|_| invalid())
+                        })
+                        .collect::<Result<Vec<MidiNote>, TuningError>>()?,
+                ),
+            },
+        };
+        self.hand_position = None;
+        Ok(self)
+    }
+    /// Assign each of the given notes (in ascending order) to a string, returning the string
+    /// numbers (see [super::fingering]) and moving the hand to the new position
+    ///
+    /// Returns `None` if no tuning is set, or the notes cannot be played together.
+    pub fn assign_strings(&mut self, notes: &[MidiNote]) -> Option<Vec<u8>> {
+        let fingering = super::fingering(notes, self.strings.as_ref()?, self.hand_position)?;
+        self.hand_position = super::hand_position(&fingering, self.hand_position);
+        Some(fingering.into_iter().map(|(string, _)| string).collect())
+    }
+    /// Set how notes are split between the hands of a piano part
+    ///
+    /// The split is either a LilyPond note in absolute octave entry from which notes go to the
+    /// right hand (e.g. `c'`), `dynamic` to split notes by what each hand can reach (see
+    /// [super::split_hands]), or `none` to not split notes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the split is not recognized, in which case the
+    /// split is not changed.
+    pub fn set_hand_split_str(&mut self, split: &str) -> Result<&mut Self, HandSplitError> {
+        self.hand_split = match split {
+            "none" => None,
+            "dynamic" => Some(HandSplit::Dynamic),
+            _ => match LilyNote::from_lilypond_str(split, &self.language) {
+                Ok(note) => Some(HandSplit::Fixed(*note.note())),
+                Err(_) => return Err(HandSplitError::InvalidHandSplitString(split.into())),
+            },
+        };
+        if self.hand_split.is_none() {
+            self.switch_hand(Hand::Right);
+        }
+        self.hand_centers = [None, None];
+        Ok(self)
+    }
+    /// Split the given notes into the notes for the right hand and the notes for the left hand,
+    /// remembering where each hand played
+    ///
+    /// All notes go to the right hand if notes are not split.
+    pub fn split_hands(
+        &mut self,
+        notes: &BTreeSet<MidiNote>,
+    ) -> (BTreeSet<MidiNote>, BTreeSet<MidiNote>) {
+        let Some(split) = &self.hand_split else {
+            return (notes.clone(), BTreeSet::new());
+        };
+        let (right, left) = super::split_hands(notes, split, self.hand_centers);
+        for (center, notes) in self.hand_centers.iter_mut().zip([&right, &left]) {
+            if let Some(new_center) = super::hand_center(notes) {
+                *center = Some(new_center);
+            }
+        }
+        (right, left)
+    }
+    /// Switch to outputting the notes of the given hand, such that relative octave entry,
+    /// octave checks and the clef continue from the previous notes of that hand
+    pub fn switch_hand(&mut self, hand: Hand) -> &mut Self {
+        if hand == self.hand {
+            return self;
+        }
+        let other = &mut self.other_hand;
+        std::mem::swap(
+            &mut self.previous_absolute_note_reference,
+            &mut other.previous_absolute_note_reference,
+        );
+        std::mem::swap(
+            &mut self.previous_absolute_note_reference_spelling,
+            &mut other.previous_absolute_note_reference_spelling,
+        );
+        std::mem::swap(
+            &mut self.octave_check_on_next_note,
+            &mut other.octave_check_on_next_note,
+        );
+        std::mem::swap(
+            &mut self.notes_since_octave_check,
+            &mut other.notes_since_octave_check,
+        );
+        std::mem::swap(&mut self.clef, &mut other.clef);
+        self.hand = hand;
+        self
+    }
+    /// Where the notes of the given hand go when splitting notes between the hands
+    pub fn hand_output(&self, hand: Hand) -> &OutputTarget {
+        match hand {
+            Hand::Right => &self.right_hand_output,
+            Hand::Left => &self.left_hand_output,
+        }
+    }
+    /// Whether notes on the given MIDI channel are used for the current output mode
+    pub fn accepts_channel(&self, channel: u8) -> bool {
+        match self.drum_channel {
+            Some(drum_channel) => {
+                (channel == drum_channel) == (self.output_mode == OutputMode::Drums)
+            }
+            None => true,
+        }
+    }
+    /// Restrict the range, and the clefs for automatic clef switching, to the ones of the given
+    /// instrument
+    pub fn set_instrument(&mut self, instrument: &Instrument) -> &mut Self {
+        self.range = Some(instrument.range());
+        self.clefs = instrument.clefs();
+        self
+    }
+    /// Set the clefs which automatic clef switching may choose from, given as a comma separated
+    /// list (e.g. `bass,tenor,treble`)
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if one of the clefs is not recognized, in which case
+    /// the clefs are not changed.
+    pub fn set_clefs_str(&mut self, clefs: &str) -> Result<&mut Self, ClefError> {
+        self.clefs = clefs
+            .split(',')
+            .map(Clef::try_from)
+            .collect::<Result<Vec<Clef>, ClefError>>()?;
+        Ok(self)
+    }
+    /// The clef to switch to for the given recent notes, each given by the range of staff steps
+    /// they span (see [LilyNote::staff_step]), or `None` to keep the current clef
+    ///
+    /// The clef is only switched once each of the last `clef_notes` notes needs more than
+    /// `clef_ledger_lines` ledger lines, and another allowed clef needs fewer of them overall.
+    /// Brief excursions hence do not make the clef flip back and forth.
+    pub fn switch_clef(&self, recent: &VecDeque<(i16, i16)>) -> Option<Clef> {
+        if !self.auto_clef || recent.is_empty() || recent.len() < self.clef_notes as usize {
+            return None;
+        }
+        let max = self.clef_ledger_lines as i16;
+        let excess = |clef: &Clef, (lowest, highest): &(i16, i16)| {
+            (-clef.ledger_lines(*lowest) - max)
+                .max(clef.ledger_lines(*highest) - max)
+                .max(0)
+        };
+        let cost = |clef: &Clef| recent.iter().map(|span| excess(clef, span)).sum::<i16>();
+        if recent.iter().any(|span| excess(&self.clef, span) == 0) {
+            return None;
+        }
+        self.clefs
+            .iter()
+            .min_by_key(|clef| cost(clef))
+            .filter(|clef| cost(clef) < cost(&self.clef))
+            .cloned()
+    }
+    /// Set the range as two absolute LilyPond notes at concert pitch, separated by a colon (e.g.
+    /// `g:e''''` for a violin)
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either note is not recognized, or if the lowest
+    /// note is above the highest one.
+    pub fn set_range_lilypond_str(&mut self, range: &str) -> Result<&mut Self, LilypondNoteError> {
+        let invalid = || LilypondNoteError::InvalidNoteString(range.into());
+        let (low, high) = range.split_once(':').ok_or_else(invalid)?;
+        let low = *LilyNote::from_lilypond_str(low, &self.language)?.note();
+        let high = *LilyNote::from_lilypond_str(high, &self.language)?.note();
+        if low > high {
+            return Err(invalid());
+        }
+        self.range = Some((low, high));
+        Ok(self)
+    }
+    /// Whether the given note at concert pitch lies within the range, if any
+    pub fn in_range(&self, note: MidiNote) -> bool {
+        self.range
+            .is_none_or(|(low, high)| (low..=high).contains(&note))
+    }
+    /// The ottava with which to write notes spanning the given staff steps (see
+    /// [LilyNote::staff_step]), given the ottava currently in effect
+    ///
+    /// An ottava is only used when notes would otherwise need more ledger lines than allowed.
+    /// Once in effect, it is kept for as long as the notes fit and remain beyond the staff, so
+    /// that it is not ended and started again over a few notes. Returns 0 (no ottava) if
+    /// ottavas are disabled.
+    pub fn ottava(&self, lowest: i16, highest: i16, current: i8) -> i8 {
+        let Some(max) = self.ottava_ledger_lines.map(i16::from) else {
+            return 0;
+        };
+        let fits = |ottava: i8| {
+            let shift = 7 * ottava as i16;
+            self.clef.ledger_lines(lowest - shift) >= -max
+                && self.clef.ledger_lines(highest - shift) <= max
+        };
+        let beyond_staff = match current.signum() {
+            1 => self.clef.ledger_lines(lowest) > 0,
+            -1 => self.clef.ledger_lines(highest) < 0,
+            _ => false,
+        };
+        if beyond_staff && fits(current) {
+            return current;
+        }
+        [0, 1, -1, 2, -2]
+            .into_iter()
+            .find(|ottava| fits(*ottava))
+            .unwrap_or(0)
+    }
     pub fn previous_absolute_note_reference(&mut self) -> Option<&MidiNote> {
         self.previous_absolute_note_reference.as_ref()
     }
+    /// Set the previous absolute note reference
+    ///
+    /// This also forgets its spelling, as it will then be derived from the key signature.
+    pub fn set_previous_absolute_note_reference(
+        &mut self,
+        previous_absolute_note_reference: Option<MidiNote>,
+    ) -> &mut Self {
+        self.previous_absolute_note_reference = previous_absolute_note_reference;
+        self.previous_absolute_note_reference_spelling = None;
+        self
+    }
     pub fn set_previous_absolute_note_reference_lilypond_str(
         &mut self,
         previous_absolute_note_reference: String,
     ) -> Result<&mut Self, LilypondNoteError> {
-        self.previous_absolute_note_reference =
-            Some(*LilyNote::from_lilypond_str(&previous_absolute_note_reference)?.note());
+        let note = LilyNote::from_lilypond_str(&previous_absolute_note_reference, &self.language)?;
+        self.previous_absolute_note_reference = Some(*note.note());
+        self.previous_absolute_note_reference_spelling = note.spelling().copied();
         Ok(self)
     }
+    /// Take over the state of LilyPond's parser at the end of the given context
+    ///
+    /// The language, octave entry mode (with its reference pitch) and previous chord are
+    /// replaced. The key signature is only replaced if the context has one, and the fixed
+    /// reference only if the context ends in a `\fixed` block.
+    pub fn set_context(&mut self, context: LilyContext) -> &mut Self {
+        let LilyContext {
+            language,
+            key,
+            octave_entry,
+            fixed_reference,
+            previous_absolute_note_reference,
+            previous_chord,
+        } = context;
+        self.language = language;
+        if let Some(key) = key {
+            // the source is written for the instrument, whereas the key is at concert pitch
+            self.key = key.transpose(self.transposition_spelling.fifths());
+        }
+        self.octave_entry = octave_entry;
+        if let Some(fixed_reference) = fixed_reference {
+            self.fixed_reference = fixed_reference;
+        }
+        self.previous_absolute_note_reference = previous_absolute_note_reference.map(|r| r.0);
+        self.previous_absolute_note_reference_spelling =
+            previous_absolute_note_reference.map(|r| r.1);
+        // an empty chord makes the previous chord be forgotten
+        self.previous_chord = Some(previous_chord.unwrap_or_default());
+        self
+    }
+    /// Parse LilyPond source, such as the text before the cursor in an editor, and take over
+    /// the state of LilyPond's parser at its end (see [LilyParameters::set_context])
+    pub fn set_context_lilypond_str(
+        &mut self,
+        context: &str,
+    ) -> Result<&mut Self, LilypondContextError> {
+        let context = LilyContext::from_lilypond_str(context, &self.language)?;
+        Ok(self.set_context(context))
+    }
 }
 
 #[derive(Debug)]
 pub enum LilyParametersError {
     NoteError(LilypondNoteError),
+    /// No alteration preset with the given name was defined
+    UnknownAlterationPreset(String),
 }
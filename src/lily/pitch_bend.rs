@@ -0,0 +1,30 @@
+use super::PitchBendError;
+
+make_lily_str_map!(
+    /// How finely pitch bends are taken into account for the notes being played
+    #[derive(Default)]
+    PitchBendResolution;
+    PitchBendError::InvalidPitchBendResolutionString;
+    /// Ignore pitch bends
+    #[default]
+    Off, "off", "o";
+    /// Round pitch bends to the nearest semitone
+    Semitone, "semitone", "s";
+    /// Round pitch bends to the nearest quarter tone
+    QuarterTone, "quarter-tone", "q";
+);
+
+impl PitchBendResolution {
+    /// Convert a raw pitch bend value into the number of quarter tones by which to bend a note
+    ///
+    /// The `value` is centered around 0 (ranging from -8192 to 8191), and `range` is the number
+    /// of semitones covered by a full bend in either direction, as configured on the device.
+    pub fn quarter_tones(&self, value: i16, range: u8) -> i16 {
+        let semitones = value as f64 / 8192.0 * range as f64;
+        match self {
+            PitchBendResolution::Off => 0,
+            PitchBendResolution::Semitone => 2 * semitones.round() as i16,
+            PitchBendResolution::QuarterTone => (2.0 * semitones).round() as i16,
+        }
+    }
+}
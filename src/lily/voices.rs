@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use crate::MidiNote;
+
+/// Milliseconds within which notes starting together are taken as a chord
+const CHORD_ONSET_WINDOW: u32 = 40;
+/// Milliseconds within which the notes of a chord are released together, such that a note held
+/// against others starting at the same time forms its own voice
+const CHORD_RELEASE_WINDOW: u32 = 120;
+/// Milliseconds by which a note may still be held after the next note of its voice started, as
+/// happens when playing legato
+const OVERLAP_TOLERANCE: u32 = 80;
+
+/// A note played while separating voices, with the times (in milliseconds) at which it was
+/// pressed and released
+#[derive(Debug)]
+struct VoiceNote {
+    note: MidiNote,
+    start: u32,
+    end: Option<u32>,
+}
+
+/// Notes which were played together as a chord (or a single note), within one voice
+#[derive(Debug)]
+struct VoiceChord {
+    notes: BTreeSet<MidiNote>,
+    start: u32,
+    end: u32,
+}
+
+impl VoiceChord {
+    fn center(&self) -> u32 {
+        self.notes.iter().map(|note| u32::from(*note)).sum::<u32>() / self.notes.len() as u32
+    }
+}
+
+/// Tracks how long notes are held, to separate overlapping notes into independent voices
+///
+/// A passage lasts from the first note being pressed until all notes were released. Notes
+/// pressed and released together form a chord. Chords overlapping each other within the passage
+/// are assigned to different voices, while a chord starting after another one ended (or while it
+/// is being released, when playing legato) may continue its voice.
+#[derive(Debug, Default)]
+pub struct VoiceSeparation {
+    notes: Vec<VoiceNote>,
+}
+
+impl VoiceSeparation {
+    /// A note was pressed at the given time in milliseconds
+    pub fn note_on(&mut self, note: MidiNote, time: u32) {
+        self.notes.push(VoiceNote {
+            note,
+            start: time,
+            end: None,
+        });
+    }
+
+    /// A note was released at the given time in milliseconds
+    pub fn note_off(&mut self, note: MidiNote, time: u32) {
+        if let Some(voice_note) = self
+            .notes
+            .iter_mut()
+            .find(|voice_note| voice_note.note == note && voice_note.end.is_none())
+        {
+            voice_note.end = Some(time);
+        }
+    }
+
+    /// Whether all notes of the passage were released
+    pub fn is_finished(&self) -> bool {
+        !self.notes.is_empty() && self.notes.iter().all(|note| note.end.is_some())
+    }
+
+    /// Take the notes of the passage as chords (or single notes), separated into voices from
+    /// the highest to the lowest
+    ///
+    /// Each chord continues the voice whose last chord ended before it started (allowing for a
+    /// slight overlap) and is closest in pitch. If all voices are still holding a note, the
+    /// chord starts a new voice.
+    pub fn take_voices(&mut self) -> Vec<Vec<BTreeSet<MidiNote>>> {
+        let mut notes = std::mem::take(&mut self.notes);
+        notes.sort_by_key(|note| note.start);
+        let mut chords: Vec<VoiceChord> = Vec::new();
+        for note in notes {
+            let end = note.end.unwrap_or(note.start);
+            let chord = chords.iter_mut().find(|chord| {
+                note.start.abs_diff(chord.start) <= CHORD_ONSET_WINDOW
+                    && end.abs_diff(chord.end) <= CHORD_RELEASE_WINDOW
+            });
+            match chord {
+                Some(chord) => {
+                    chord.notes.insert(note.note);
+                }
+                None => chords.push(VoiceChord {
+                    notes: BTreeSet::from([note.note]),
+                    start: note.start,
+                    end,
+                }),
+            }
+        }
+        let mut voices: Vec<Vec<VoiceChord>> = Vec::new();
+        for chord in chords {
+            let voice = voices
+                .iter_mut()
+                .filter(|voice| {
+                    voice.last().is_some_and(|last| {
+                        last.start < chord.start && last.end <= chord.start + OVERLAP_TOLERANCE
+                    })
+                })
+                .min_by_key(|voice| {
+                    voice
+                        .last()
+                        .map(|last| last.center().abs_diff(chord.center()))
+                        .unwrap_or_default()
+                });
+            match voice {
+                Some(voice) => voice.push(chord),
+                None => voices.push(vec![chord]),
+            }
+        }
+        let average = |voice: &Vec<VoiceChord>| {
+            voice.iter().map(VoiceChord::center).sum::<u32>() / voice.len() as u32
+        };
+        voices.sort_by_key(|voice| std::cmp::Reverse(average(voice)));
+        voices
+            .into_iter()
+            .map(|voice| voice.into_iter().map(|chord| chord.notes).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn separate(notes: &[(MidiNote, u32, u32)]) -> Vec<Vec<Vec<MidiNote>>> {
+        let mut separation = VoiceSeparation::default();
+        let mut events: Vec<(u32, bool, MidiNote)> = notes
+            .iter()
+            .flat_map(|(note, start, end)| [(*start, true, *note), (*end, false, *note)])
+            .collect();
+        events.sort();
+        for (time, on, note) in events {
+            match on {
+                true => separation.note_on(note, time),
+                false => separation.note_off(note, time),
+            }
+        }
+        assert!(separation.is_finished());
+        separation
+            .take_voices()
+            .into_iter()
+            .map(|voice| {
+                voice
+                    .into_iter()
+                    .map(|chord| chord.into_iter().collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_a_legato_line_in_one_voice() {
+        let voices = separate(&[(60, 0, 520), (62, 500, 1030), (64, 1000, 1500)]);
+        assert_eq!(voices, vec![vec![vec![60], vec![62], vec![64]]]);
+    }
+
+    #[test]
+    fn keeps_a_block_chord_in_one_voice() {
+        let voices = separate(&[(60, 0, 1000), (64, 12, 1010), (67, 25, 980)]);
+        assert_eq!(voices, vec![vec![vec![60, 64, 67]]]);
+    }
+
+    #[test]
+    fn separates_a_held_note_from_a_moving_line() {
+        let voices = separate(&[
+            (55, 0, 2000),
+            (60, 10, 510),
+            (62, 500, 1010),
+            (64, 1000, 1990),
+        ]);
+        assert_eq!(
+            voices,
+            vec![vec![vec![60], vec![62], vec![64]], vec![vec![55]]]
+        );
+    }
+}
@@ -1,19 +1,36 @@
 use std::{
-    collections::{BTreeSet, HashMap},
-    sync::{Arc, Mutex},
+    collections::{BTreeSet, HashMap, VecDeque},
+    sync::{mpsc, Arc, Mutex},
 };
 
 use clap::{arg, command, value_parser, ArgAction};
 use lilypond_midi_input::{
+    binding::{BindingError, Bindings},
     echoerr, echoinfo,
-    lily::{self, Language, LilyAccidental, LilyKeySignature, OctaveEntry},
+    lily::{
+        self, Clef, Hand, Instrument, Language, LilyAccidental, LilyKeySignature, OctaveCheckStyle,
+        OctaveEntry, PitchBendResolution,
+    },
     midi::{self, list_input_devices},
-    output, InputMode, ListOptions, MidiNote,
+    output,
+    output::{OutputTarget, OutputTargetError},
+    zone::Zone,
+    InputMode, ListOptions, MidiNote, OutputMode,
 };
 use regex::Regex;
 
 const BUFFER_SIZE: usize = 1024;
 
+/// Keys on stdin whose value takes up the rest of the line, as it may contain spaces
+const REST_OF_LINE_KEYS: [&str; 6] = [
+    "alterations",
+    "alt",
+    "global-alterations",
+    "galt",
+    "alt-preset-define",
+    "altpd",
+];
+
 fn main() {
     let matches = command!()
         .arg_required_else_help(true)
@@ -32,6 +49,44 @@ fn main() {
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(InputMode))
                 .default_value("single"),
+            arg!(--"output-mode" "What to output for the notes")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(OutputMode))
+                .default_value("notes"),
+            arg!(--"drum-channel" <channel> "MIDI channel dedicated to drums (1-16), or any")
+                .action(ArgAction::Set)
+                .default_value("10"),
+            arg!(--"drum-map" <names> "Drum names overriding the General MIDI percussion map")
+                .action(ArgAction::Set),
+            arg!(--"chord-name-preference" <criteria> "Order of criteria for naming ambiguous chords")
+                .action(ArgAction::Set)
+                .default_value("bass,thirds,simple"),
+            arg!(--"figures-output" <target> "Where figures go in figured bass output: tag:<name> or file:<path>")
+                .action(ArgAction::Set)
+                .default_value("tag:figures"),
+            arg!(--keyswitches <actions> "Keys reserved for commands instead of notes, like 21:rest,22:undo")
+                .action(ArgAction::Set),
+            arg!(--modifiers <suffixes> "Suffixes added to notes while controls are held, like cc67:!,note21:~")
+                .action(ArgAction::Set),
+            arg!(--"undo-output" <target> "Where undone output is announced: tag:<name> or file:<path>")
+                .action(ArgAction::Set)
+                .default_value("tag:undo"),
+            arg!(--"status-output" <target> "Where state like the octave shift is reported: tag:<name> or file:<path>")
+                .action(ArgAction::Set),
+            arg!(--zone <zone> "Keyboard zone entered with its own parameters: name:lowest:highest[:channel]")
+                .action(ArgAction::Append),
+            arg!(--bind <binding> "Bind a MIDI message to a command: <trigger>><command>, like cc20:127>mode=chord")
+                .action(ArgAction::Append),
+            arg!(--config <path> "File with lines of settings like on stdin, applied before reading stdin")
+                .action(ArgAction::Set),
+            arg!(--"hand-split" <split> "Split notes between the hands from a given note upwards, or dynamic")
+                .action(ArgAction::Set),
+            arg!(--"right-hand-output" <target> "Where the right hand goes when splitting: tag:<name> or file:<path>")
+                .action(ArgAction::Set)
+                .default_value("tag:rh"),
+            arg!(--"left-hand-output" <target> "Where the left hand goes when splitting: tag:<name> or file:<path>")
+                .action(ArgAction::Set)
+                .default_value("tag:lh"),
             arg!(--language "Note name language to use")
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(Language))
@@ -40,6 +95,44 @@ fn main() {
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(OctaveEntry))
                 .default_value("absolute"),
+            arg!(--"fixed-reference" <note> "Reference pitch for fixed octave entry")
+                .action(ArgAction::Set)
+                .default_value("c'"),
+            arg!(--transposition <note> "Sounding pitch of a written c' for transposing instruments")
+                .action(ArgAction::Set)
+                .default_value("c'"),
+            arg!(--"transposition-command" "Output a \\transposition command when it changes")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(bool))
+                .default_value("false"),
+            arg!(--instrument "Instrument whose range notes are checked against")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(Instrument)),
+            arg!(--range <range> "Custom range as lowest:highest note at concert pitch")
+                .action(ArgAction::Set),
+            arg!(--tuning <tuning> "Tuning of the strings to write string numbers for, or a list of open strings as lowest:...:highest note")
+                .action(ArgAction::Set),
+            arg!(--clef "Clef of the staff, used for counting ledger lines")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(Clef))
+                .default_value("treble"),
+            arg!(--"auto-clef" "Switch between the allowed clefs depending on the register")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(bool))
+                .default_value("false"),
+            arg!(--clefs "Comma separated clefs which automatic clef switching may choose from")
+                .action(ArgAction::Set),
+            arg!(--"clef-ledger-lines" <lines> "Ledger lines a note may need before switching clefs")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(u8))
+                .default_value("2"),
+            arg!(--"clef-notes" <notes> "Consecutive notes exceeding the ledger lines to switch clefs")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(u8))
+                .default_value("3"),
+            arg!(--ottava <lines> "Insert an \\ottava beyond this many ledger lines, or off")
+                .action(ArgAction::Set)
+                .default_value("off"),
             arg!(--"octave-check-notes" "Whether to add octave checks to the notes")
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(bool))
@@ -48,9 +141,48 @@ fn main() {
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(bool))
                 .default_value("false"),
+            arg!(--"octave-check-every" <notes> "Add an octave check on every n-th note, or never if 0")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(u8))
+                .default_value("0"),
+            arg!(--"octave-check-leaps" "Add octave checks on notes reached by leaps over an octave")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(bool))
+                .default_value("false"),
+            arg!(--"octave-check-chords" "Add octave checks on the first note of each chord")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(bool))
+                .default_value("false"),
+            arg!(--"octave-check-after-q" "Add octave checks on the note following a q")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(bool))
+                .default_value("false"),
+            arg!(--"octave-check-style" "Write octave checks inline or as \\octaveCheck commands")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(OctaveCheckStyle))
+                .default_value("inline"),
             arg!(--alterations "Custom alterations within an octave").action(ArgAction::Set),
             arg!(--"global-alterations" <alterations> "Global alterations over all notes")
                 .action(ArgAction::Set),
+            arg!(--"alt-preset-file" <file> "File with alteration presets, one per line")
+                .action(ArgAction::Set),
+            arg!(--"alt-preset" <name> "Alteration preset to use").action(ArgAction::Set),
+            arg!(--spelling "Spelling overrides within an octave").action(ArgAction::Set),
+            arg!(--"global-spelling" <spelling> "Spelling overrides for specific notes")
+                .action(ArgAction::Set),
+            arg!(--"pitch-bend-resolution" "How finely to take pitch bends into account")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(PitchBendResolution))
+                .default_value("off"),
+            arg!(--"pitch-bend-range" "Semitones covered by a full pitch bend on the device")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(u8))
+                .default_value("2"),
+            arg!(--"octave-shift" <octaves> "Octaves by which to shift the notes played, e.g. -1 or 2")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(i8))
+                .allow_negative_numbers(true)
+                .default_value("0"),
         ])
         .args([
             arg!(-l --"list-devices" "List available MIDI input devices").exclusive(true),
@@ -61,10 +193,21 @@ fn main() {
                     "key",
                     "accidentals",
                     "mode",
+                    "output-mode",
+                    "chord-name-preference",
                     "language",
                     "octave-entry",
+                    "instrument",
+                    "tuning",
+                    "keyswitch",
+                    "clef",
                     "octave-check-notes",
                     "octave-check-on-next-note",
+                    "octave-check-leaps",
+                    "octave-check-chords",
+                    "octave-check-after-q",
+                    "octave-check-style",
+                    "pitch-bend-resolution",
                 ]),
             arg!(--"raw-midi" "Display raw MIDI events instead of LilyPond notes"),
         ])
@@ -85,9 +228,21 @@ fn main() {
             "key" => LilyKeySignature::list_options(),
             "accidentals" => LilyAccidental::list_options(),
             "mode" => InputMode::list_options(),
+            "output-mode" => OutputMode::list_options(),
+            "chord-name-preference" => lily::ChordNamePreference::list_options(),
             "language" => Language::list_options(),
             "octave-entry" => OctaveEntry::list_options(),
-            "octave-check-notes" | "octave-check-on-next-note" => {
+            "instrument" => Instrument::list_options(),
+            "tuning" => lily::Tuning::list_options(),
+            "keyswitch" => lily::KeyswitchAction::list_options(),
+            "clef" => Clef::list_options(),
+            "pitch-bend-resolution" => PitchBendResolution::list_options(),
+            "octave-check-style" => OctaveCheckStyle::list_options(),
+            "octave-check-notes"
+            | "octave-check-on-next-note"
+            | "octave-check-leaps"
+            | "octave-check-chords"
+            | "octave-check-after-q" => {
                 output!("{} {}", "True", "true");
                 output!("{} {}", "False", "false");
             }
@@ -151,7 +306,273 @@ fn main() {
                 None => HashMap::new(),
             },
         ) {
-            Ok(p) => p,
+            Ok(mut p) => {
+                if let Err(e) = p.set_fixed_reference_lilypond_str(
+                    matches
+                        .get_one::<String>("fixed-reference")
+                        .expect("fixed reference is given"),
+                ) {
+                    echoerr!("An invalid parameter was given: {:?}", e);
+                    return;
+                }
+                if let Err(e) = p.set_transposition_lilypond_str(
+                    matches
+                        .get_one::<String>("transposition")
+                        .expect("transposition is given"),
+                ) {
+                    echoerr!("An invalid parameter was given: {:?}", e);
+                    return;
+                }
+                p.set_transposition_command(
+                    *matches
+                        .get_one::<bool>("transposition-command")
+                        .expect("transposition command is given and valid"),
+                );
+                p.set_output_mode(
+                    matches
+                        .get_one::<OutputMode>("output-mode")
+                        .expect("output mode is given and valid")
+                        .clone(),
+                );
+                match parse_drum_channel(
+                    matches
+                        .get_one::<String>("drum-channel")
+                        .expect("drum channel is given"),
+                ) {
+                    Some(channel) => {
+                        p.set_drum_channel(channel);
+                    }
+                    None => return,
+                }
+                if let Err(e) = p.set_chord_name_preferences_str(
+                    matches
+                        .get_one::<String>("chord-name-preference")
+                        .expect("chord name preference is given"),
+                ) {
+                    echoerr!("An invalid parameter was given: {:?}", e);
+                    return;
+                }
+                match matches
+                    .get_one::<String>("figures-output")
+                    .expect("figures output is given")
+                    .parse::<OutputTarget>()
+                {
+                    Ok(target) => {
+                        p.set_figures_output(target);
+                    }
+                    Err(e) => {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                for (key, hand) in [
+                    ("right-hand-output", Hand::Right),
+                    ("left-hand-output", Hand::Left),
+                ] {
+                    match matches
+                        .get_one::<String>(key)
+                        .expect("hand output is given")
+                        .parse::<OutputTarget>()
+                    {
+                        Ok(target) => match hand {
+                            Hand::Right => p.set_right_hand_output(target),
+                            Hand::Left => p.set_left_hand_output(target),
+                        },
+                        Err(e) => {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    };
+                }
+                if let Some(split) = matches.get_one::<String>("hand-split") {
+                    if let Err(e) = p.set_hand_split_str(split) {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                if let Some(target) = matches.get_one::<String>("status-output") {
+                    match target.parse::<OutputTarget>() {
+                        Ok(target) => {
+                            p.set_status_output(Some(target));
+                        }
+                        Err(e) => {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                match matches
+                    .get_one::<String>("undo-output")
+                    .expect("undo output is given")
+                    .parse::<OutputTarget>()
+                {
+                    Ok(target) => {
+                        p.set_undo_output(target);
+                    }
+                    Err(e) => {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                if let Some(actions) = matches.get_one::<String>("keyswitches") {
+                    let Some(actions) = parse_subkeys(&re_subkeyval, actions) else {
+                        return;
+                    };
+                    for (note, action) in actions {
+                        if let Err(e) = p.add_keyswitch(note, &action) {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                if let Some(modifiers) = matches.get_one::<String>("modifiers") {
+                    for cap in re_subkeyval.captures_iter(modifiers) {
+                        let control = cap.name("key").expect("Valid named group").as_str();
+                        let suffix = cap.name("value").expect("Valid named group").as_str();
+                        if let Err(e) = p.add_modifier(control, suffix) {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                if let Some(names) = matches.get_one::<String>("drum-map") {
+                    for (note, name) in
+                        parse_subkeys(&re_subkeyval, names).expect("All of the subkeys are numbers")
+                    {
+                        p.add_drum_name(note, name);
+                    }
+                }
+                p.set_octave_check_every(
+                    *matches
+                        .get_one::<u8>("octave-check-every")
+                        .expect("octave check every is given and valid"),
+                )
+                .set_octave_check_leaps(
+                    *matches
+                        .get_one::<bool>("octave-check-leaps")
+                        .expect("octave check leaps is given and valid"),
+                )
+                .set_octave_check_chords(
+                    *matches
+                        .get_one::<bool>("octave-check-chords")
+                        .expect("octave check chords is given and valid"),
+                )
+                .set_octave_check_after_q(
+                    *matches
+                        .get_one::<bool>("octave-check-after-q")
+                        .expect("octave check after q is given and valid"),
+                )
+                .set_octave_check_style(
+                    matches
+                        .get_one::<OctaveCheckStyle>("octave-check-style")
+                        .expect("octave check style is given and valid")
+                        .clone(),
+                );
+                if let Some(instrument) = matches.get_one::<Instrument>("instrument") {
+                    p.set_instrument(instrument);
+                }
+                if let Some(range) = matches.get_one::<String>("range") {
+                    if let Err(e) = p.set_range_lilypond_str(range) {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                if let Some(tuning) = matches.get_one::<String>("tuning") {
+                    if let Err(e) = p.set_tuning_str(tuning) {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                p.set_clef(
+                    matches
+                        .get_one::<Clef>("clef")
+                        .expect("clef is given and valid")
+                        .clone(),
+                );
+                if let Some(clefs) = matches.get_one::<String>("clefs") {
+                    if let Err(e) = p.set_clefs_str(clefs) {
+                        echoerr!("An invalid parameter was given: {:?}", e);
+                        return;
+                    }
+                }
+                p.set_auto_clef(
+                    *matches
+                        .get_one::<bool>("auto-clef")
+                        .expect("auto clef is given and valid"),
+                )
+                .set_clef_ledger_lines(
+                    *matches
+                        .get_one::<u8>("clef-ledger-lines")
+                        .expect("clef ledger lines is given and valid"),
+                )
+                .set_clef_notes(
+                    *matches
+                        .get_one::<u8>("clef-notes")
+                        .expect("clef notes is given and valid"),
+                );
+                match parse_ottava(
+                    matches
+                        .get_one::<String>("ottava")
+                        .expect("ottava is given"),
+                ) {
+                    Some(ottava) => {
+                        p.set_ottava_ledger_lines(ottava);
+                    }
+                    None => return,
+                }
+                if let Some(file) = matches.get_one::<String>("alt-preset-file") {
+                    if !load_alteration_presets(&mut p, &re_subkeyval, file) {
+                        return;
+                    }
+                }
+                if let Err(e) =
+                    p.set_alteration_preset(matches.get_one::<String>("alt-preset").cloned())
+                {
+                    echoerr!("An invalid parameter was given: {:?}", e);
+                    return;
+                }
+                if let Some(spellings) = matches.get_one::<String>("spelling") {
+                    for (note, value) in parse_subkeys(&re_subkeyval, spellings)
+                        .expect("All of the subkeys are numbers")
+                    {
+                        if let Err(e) = p.add_spelling(note, &value) {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                if let Some(spellings) = matches.get_one::<String>("global-spelling") {
+                    for (note, value) in parse_subkeys(&re_subkeyval, spellings)
+                        .expect("All of the subkeys are numbers")
+                    {
+                        if let Err(e) = p.add_global_spelling(note, &value) {
+                            echoerr!("An invalid parameter was given: {:?}", e);
+                            return;
+                        }
+                    }
+                }
+                p.set_pitch_bend_resolution(
+                    matches
+                        .get_one::<PitchBendResolution>("pitch-bend-resolution")
+                        .expect("pitch bend resolution is given and valid")
+                        .clone(),
+                )
+                .set_pitch_bend_range(
+                    *matches
+                        .get_one::<u8>("pitch-bend-range")
+                        .expect("pitch bend range is given and valid"),
+                )
+                .set_octave_shift(
+                    *matches
+                        .get_one::<i8>("octave-shift")
+                        .expect("octave shift is given and valid"),
+                );
+                // the default shift is not worth reporting
+                if *p.octave_shift() != 0 {
+                    p.report_octave_shift();
+                }
+                p
+            }
             Err(e) => {
                 echoerr!("An invalid parameter was given: {:?}", e);
                 return;
@@ -159,7 +580,42 @@ fn main() {
         },
     ));
 
+    let mut zones: Vec<Zone> = Vec::new();
+    if let Some(definitions) = matches.get_many::<String>("zone") {
+        let params = lily_parameters.lock().expect("Received the mutex lock");
+        for definition in definitions {
+            match Zone::new(definition, &params) {
+                Ok(zone) => zones.push(zone),
+                Err(e) => {
+                    echoerr!("An invalid parameter was given: {:?}", e);
+                    return;
+                }
+            }
+        }
+    }
+    let lily_zones: Arc<Mutex<Vec<Zone>>> = Arc::new(Mutex::new(zones));
+    // the notes played in each zone and their output state, by the name of the zone, which are
+    // forgotten when the zone is removed or redefined
+    let lily_zone_engines: Arc<Mutex<HashMap<String, Engine>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    let config = matches.get_one::<String>("config").cloned();
+
+    let mut bindings = Bindings::default();
+    for definition in matches.get_many::<String>("bind").unwrap_or_default() {
+        if let Err(e) = bindings.bind(definition) {
+            echoerr!("An invalid parameter was given: {:?}", e);
+            return;
+        }
+    }
+    let lily_bindings: Arc<Mutex<Bindings>> = Arc::new(Mutex::new(bindings));
+    // lines of settings, from the config file and stdin, and from commands bound to MIDI messages
+    let (commands, lines) = mpsc::channel::<String>();
+
     let parameters = Arc::clone(&lily_parameters);
+    let zones = Arc::clone(&lily_zones);
+    let zone_engines = Arc::clone(&lily_zone_engines);
+    let bindings = Arc::clone(&lily_bindings);
+    let bound_commands = commands.clone();
     let lilypond_midi_input_handler = std::thread::spawn(move || {
         let name = matches
             .get_one::<String>("DEVICE")
@@ -175,14 +631,12 @@ fn main() {
 
         port.clear();
 
-        // track notes to be put into a chord
-        let mut notes: BTreeSet<MidiNote> = BTreeSet::new();
-        // track notes being pressed to know when everything was released
-        let mut pressed: BTreeSet<MidiNote> = BTreeSet::new();
+        // track the notes played outside of any zone
+        let mut engine = Engine::default();
         // track pedals being pressed to know when everything was released
         let mut pedals: BTreeSet<MidiNote> = BTreeSet::new();
-        // track last chord inserted (to insert a 'q' on repetition)
-        let mut last_chord: Option<BTreeSet<MidiNote>> = None;
+        // track current position of the pitch bend wheel
+        let mut pitch_bend: i16 = 0;
         if *matches.get_one::<bool>("raw-midi").unwrap_or(&false) {
             port.listen(|event| {
                 output!("{:?}", event);
@@ -192,131 +646,333 @@ fn main() {
         }
         port.listen_mut(|event| {
             let mut params = parameters.lock().expect("Received the mutex lock");
-            let use_chords: bool = match params.mode() {
-                InputMode::Single => false,
-                InputMode::Chord => true,
-                InputMode::PedalChord => !pedals.is_empty(),
-                InputMode::PedalSingle => pedals.is_empty(),
-            };
-            if let Some(prev_chord) = params.take_previous_chord() {
-                match prev_chord.is_empty() {
-                    true => last_chord = None,
-                    false => last_chord = Some(prev_chord),
+            let mut zones = zones.lock().expect("Received the mutex lock");
+            let mut zone_engines = zone_engines.lock().expect("Received the mutex lock");
+            let mut bindings = bindings.lock().expect("Received the mutex lock");
+            let time = event.timestamp;
+            let message = midi::MidiMessageType::from(event);
+            if let Some(binding) = bindings.learn_from(&message) {
+                echoinfo!("Update bind={binding}");
+                return;
+            }
+            if let Some(command) = bindings.command(&message) {
+                // the release of a bound note only keeps it from being entered
+                if !matches!(message, midi::MidiMessageType::NoteOff { .. }) {
+                    match command.parse::<lily::KeyswitchAction>() {
+                        Ok(action) => {
+                            // a bound note in a zone acts on the zone, like the notes played in it
+                            let zone = match message {
+                                midi::MidiMessageType::NoteOn { note, channel, .. } => {
+                                    zones.iter_mut().find(|zone| zone.contains(note, channel))
+                                }
+                                _ => None,
+                            };
+                            match zone {
+                                Some(zone) => {
+                                    let engine =
+                                        zone_engines.entry(zone.name().clone()).or_default();
+                                    let target = zone.output().clone();
+                                    run_keyswitch(
+                                        action,
+                                        zone.parameters_mut(),
+                                        &mut engine.staves,
+                                        &mut engine.history,
+                                        Some(&target),
+                                    )
+                                }
+                                None => run_keyswitch(
+                                    action,
+                                    &mut params,
+                                    &mut engine.staves,
+                                    &mut engine.history,
+                                    None,
+                                ),
+                            }
+                        }
+                        Err(_) => bound_commands
+                            .send(command.replace(';', " "))
+                            .expect("Settings are being read"),
+                    }
                 }
+                return;
             }
-            match midi::MidiMessageType::from(event) {
-                midi::MidiMessageType::NoteOn { note, .. } => {
-                    pressed.insert(note);
-                    notes.insert(note);
+            let (note, channel) = match message {
+                midi::MidiMessageType::NoteOn { note, channel, .. }
+                | midi::MidiMessageType::NoteOff { note, channel, .. } => (note, channel),
+                midi::MidiMessageType::PedalOn { pedal, .. } => {
+                    if !hold_modifier(&mut params, &mut zones, pedal, true) {
+                        pedals.insert(pedal);
+                    }
+                    return;
                 }
-                midi::MidiMessageType::NoteOff { note, .. } => {
-                    pressed.remove(&note);
+                midi::MidiMessageType::PedalOff { pedal, .. } => {
+                    if !hold_modifier(&mut params, &mut zones, pedal, false) {
+                        pedals.remove(&pedal);
+                    }
+                    return;
                 }
-                midi::MidiMessageType::PedalOn { pedal, .. } => {
-                    pedals.insert(pedal);
+                midi::MidiMessageType::ControlChange {
+                    controller, value, ..
+                } => {
+                    hold_modifier(&mut params, &mut zones, controller, value > 0);
                     return;
                 }
-                midi::MidiMessageType::PedalOff { pedal } => {
-                    pedals.remove(&pedal);
+                midi::MidiMessageType::PitchBend { value } => {
+                    pitch_bend = value;
                     return;
                 }
-                midi::MidiMessageType::Unknown => todo!(),
-            }
-            match use_chords {
-                true => {
-                    if pressed.is_empty() {
-                        match notes.len().cmp(&1) {
-                            std::cmp::Ordering::Less => (),
-                            std::cmp::Ordering::Equal => {
-                                let note = notes.pop_first().expect("A note was pressed");
-                                let lilynote = lily::LilyNote::new(note, &params);
-                                output!("{lilynote}");
-                                params.set_previous_absolute_note_reference(Some(note));
-                                params.set_octave_check_on_next_note(false);
-                            }
-                            std::cmp::Ordering::Greater => {
-                                let previous_panr =
-                                    params.previous_absolute_note_reference().cloned();
-                                let previous_oconn = *params.octave_check_on_next_note();
-                                let chord: String = notes
-                                    .iter()
-                                    .map(|note| {
-                                        let lily_note =
-                                            lily::LilyNote::new(*note, &params).to_string();
-                                        // Need to calculate relative octave among notes in chord
-                                        params.set_previous_absolute_note_reference(Some(*note));
-                                        params.set_octave_check_on_next_note(false);
-                                        lily_note
-                                    })
-                                    .collect::<Vec<String>>()
-                                    .join(" ");
-                                match last_chord.as_ref() == Some(&notes) {
-                                    true => {
-                                        output!("q");
-                                        // q should not modify the panr
-                                        params.set_previous_absolute_note_reference(previous_panr);
-                                        params.set_octave_check_on_next_note(previous_oconn);
-                                    }
-                                    false => {
-                                        output!("<{}>", chord);
-                                        last_chord = Some(notes.clone());
-                                        // Set to first note in the chord
-                                        params.set_previous_absolute_note_reference(Some(
-                                            *notes.first().expect("At least one note is given"),
-                                        ));
-                                        params.set_octave_check_on_next_note(false);
-                                    }
-                                }
-                                notes.clear();
-                            }
-                        }
-                    }
+                // program changes only act when they are bound
+                midi::MidiMessageType::ProgramChange { .. } | midi::MidiMessageType::Unknown => {
+                    return
                 }
-                false => {
-                    if !notes.is_empty() {
-                        let note = notes.pop_first().expect("A note was pressed");
-                        let lilynote = lily::LilyNote::new(note, &params);
-                        output!("{lilynote}");
-                        params.set_previous_absolute_note_reference(Some(note));
-                        params.set_octave_check_on_next_note(false);
-                    }
+            };
+            // notes played in a zone are entered with the zone's own parameters and state
+            match zones.iter_mut().find(|zone| zone.contains(note, channel)) {
+                Some(zone) => {
+                    let engine = zone_engines.entry(zone.name().clone()).or_default();
+                    let target = zone.output().clone();
+                    let params = zone.parameters_mut();
+                    let target = Some(&target);
+                    handle_note(params, engine, &pedals, pitch_bend, message, time, target);
                 }
+                None => handle_note(
+                    &mut params,
+                    &mut engine,
+                    &pedals,
+                    pitch_bend,
+                    message,
+                    time,
+                    None,
+                ),
             }
         })
         .expect("Polling for new messages works.");
     });
 
+    let _stdin_handler = std::thread::spawn(move || {
+        // settings in the config file are applied as if they were given on stdin first
+        let config: Vec<String> = match config {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(config) => config.lines().map(String::from).collect(),
+                Err(e) => {
+                    echoerr!("Could not read the config file {path}: {e}");
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        let stdin = std::io::stdin()
+            .lines()
+            .map(|l| l.expect("Managed to read stdin line"));
+        for line in config.into_iter().chain(stdin) {
+            if commands.send(line).is_err() {
+                return;
+            }
+        }
+    });
+
     let parameters = Arc::clone(&lily_parameters);
+    let zones = Arc::clone(&lily_zones);
+    let zone_engines = Arc::clone(&lily_zone_engines);
+    let bindings = Arc::clone(&lily_bindings);
     let _user_input_handler = std::thread::spawn(move || {
-        for line in std::io::stdin()
-            .lines()
-            .map(|l| l.expect("Managed to read stdin line"))
-        {
-            let mut params = parameters.lock().expect("Received the mutex lock");
-            for cap in re_keyval.captures_iter(line.as_str()) {
-                let key = cap.name("key").expect("Valid named group").as_str();
-                let value = cap.name("value").expect("Valid named group").as_str();
-                match key {
-                    "key" | "k" => {
-                        params.set_key(match value.try_into() {
-                            Ok(v) => {
-                                echoinfo!("Update key={:?}", v);
-                                v
-                            }
-                            Err(e) => match e {
-                                lily::LilypondNoteError::OutsideOctave(_) => {
-                                    panic!("This error will not occur here.")
-                                }
-                                lily::LilypondNoteError::InvalidKeyString(key) => {
-                                    echoerr!("Invalid key provided: {key}");
-                                    continue;
+        let mut lines = lines.into_iter();
+        // the zone whose parameters are changed, or `None` for the notes outside of any zone
+        let mut selected: Option<String> = None;
+        while let Some(line) = lines.next() {
+            // LilyPond source contains spaces, hence it takes up the rest of the line (or the
+            // given number of following lines) instead of being a regular key=value pair
+            if let Some(context) = line.strip_prefix("context=") {
+                let mut params = parameters.lock().expect("Received the mutex lock");
+                let mut zones = zones.lock().expect("Received the mutex lock");
+                sync_context(
+                    selected_parameters(&mut params, &mut zones, &selected),
+                    context,
+                );
+                continue;
+            }
+            if let Some(count) = line.strip_prefix("context-lines=") {
+                match count.trim().parse::<usize>() {
+                    Ok(count) => {
+                        let context: Vec<String> = lines.by_ref().take(count).collect();
+                        let mut params = parameters.lock().expect("Received the mutex lock");
+                        let mut zones = zones.lock().expect("Received the mutex lock");
+                        sync_context(
+                            selected_parameters(&mut params, &mut zones, &selected),
+                            &context.join("\n"),
+                        );
+                    }
+                    Err(_) => {
+                        echoerr!("Number of context lines is not an unsigned number: {count}")
+                    }
+                }
+                continue;
+            }
+            let mut main_params = parameters.lock().expect("Received the mutex lock");
+            let mut zones = zones.lock().expect("Received the mutex lock");
+            let mut zone_engines = zone_engines.lock().expect("Received the mutex lock");
+            // alterations may contain spaces (e.g. `\parenthesize {note}`), hence they take up
+            // the rest of the line instead of being a regular key=value pair
+            let pairs: Vec<(&str, &str)> = match line.split_once('=') {
+                Some((key, value)) if REST_OF_LINE_KEYS.contains(&key) => vec![(key, value.trim())],
+                _ => re_keyval
+                    .captures_iter(line.as_str())
+                    .map(|cap| {
+                        (
+                            cap.name("key").expect("Valid named group").as_str(),
+                            cap.name("value").expect("Valid named group").as_str(),
+                        )
+                    })
+                    .collect(),
+            };
+            for (key, value) in pairs {
+                match (key, value) {
+                    ("zone", _) => {
+                        let name = value.split(':').next().unwrap_or_default();
+                        match zones.iter_mut().find(|zone| zone.name() == name) {
+                            Some(zone) => match zone.redefine(value) {
+                                Ok(zone) => {
+                                    // notes held in the old range would never be released
+                                    zone_engines.remove(name);
+                                    echoinfo!(
+                                        "Update zone={}:{:?}:{:?}",
+                                        name,
+                                        zone.range(),
+                                        zone.channel()
+                                    )
                                 }
-                                lily::LilypondNoteError::InvalidNoteString(_) => {
-                                    panic!("This error should not occur here.")
+                                Err(e) => echoerr!("Invalid zone provided: {:?}", e),
+                            },
+                            None => match Zone::new(value, &main_params) {
+                                Ok(zone) => {
+                                    echoinfo!(
+                                        "Update zone={}:{:?}:{:?}",
+                                        name,
+                                        zone.range(),
+                                        zone.channel()
+                                    );
+                                    zones.push(zone);
                                 }
+                                Err(e) => echoerr!("Invalid zone provided: {:?}", e),
                             },
-                        });
+                        }
+                        continue;
+                    }
+                    ("zone-select", "none") => {
+                        selected = None;
+                        echoinfo!("Update zone-select=none");
+                        continue;
+                    }
+                    ("zone-select", _) => {
+                        match zones.iter().any(|zone| zone.name() == value) {
+                            true => {
+                                selected = Some(value.into());
+                                echoinfo!("Update zone-select={value}");
+                            }
+                            false => echoerr!("No zone with this name exists: {value}"),
+                        }
+                        continue;
+                    }
+                    ("zone-output", _) => {
+                        let zone = selected
+                            .as_ref()
+                            .and_then(|name| zones.iter_mut().find(|zone| zone.name() == name));
+                        match (zone, value.parse::<OutputTarget>()) {
+                            (Some(zone), Ok(target)) => {
+                                echoinfo!("Update zone-output={:?}", target);
+                                zone.set_output(target);
+                            }
+                            (None, _) => echoerr!("No zone is selected"),
+                            (_, Err(OutputTargetError::InvalidOutputTargetString(t))) => {
+                                echoerr!("Invalid zone-output provided: {t}")
+                            }
+                        }
+                        continue;
+                    }
+                    ("zone-remove", _) => {
+                        zones.retain(|zone| zone.name() != value);
+                        // a zone defined with the same name later starts out without notes
+                        zone_engines.remove(value);
+                        if selected.as_deref() == Some(value) {
+                            selected = None;
+                        }
+                        echoinfo!("Removed zone {value}");
+                        continue;
+                    }
+                    ("list", "zones") => {
+                        for zone in zones.iter() {
+                            echoinfo!(
+                                "Zone {} = {:?} on channel {:?} to {:?}",
+                                zone.name(),
+                                zone.range(),
+                                zone.channel(),
+                                zone.output()
+                            );
+                        }
+                        echoinfo!("Selected zone = {:?}", selected);
+                        continue;
+                    }
+                    ("bind", "clear") => {
+                        bindings.lock().expect("Received the mutex lock").clear();
+                        echoinfo!("Cleared all bindings");
+                        continue;
+                    }
+                    ("bind", _) => {
+                        match bindings
+                            .lock()
+                            .expect("Received the mutex lock")
+                            .bind(value)
+                        {
+                            Ok(binding) => echoinfo!("Update bind={binding}"),
+                            Err(
+                                BindingError::InvalidBindingString(b)
+                                | BindingError::InvalidTriggerString(b),
+                            ) => echoerr!("Invalid bind provided: {b}"),
+                        }
+                        continue;
+                    }
+                    ("unbind", _) => {
+                        match bindings
+                            .lock()
+                            .expect("Received the mutex lock")
+                            .unbind(value)
+                        {
+                            Ok(true) => echoinfo!("Removed binding of {value}"),
+                            Ok(false) => echoerr!("Nothing is bound to {value}"),
+                            Err(
+                                BindingError::InvalidBindingString(t)
+                                | BindingError::InvalidTriggerString(t),
+                            ) => echoerr!("Invalid unbind provided: {t}"),
+                        }
+                        continue;
                     }
+                    ("learn", _) => {
+                        bindings
+                            .lock()
+                            .expect("Received the mutex lock")
+                            .learn(value);
+                        echoinfo!("Binding {value} to the next controller or program change");
+                        continue;
+                    }
+                    ("list", "bindings") => {
+                        let bindings = bindings.lock().expect("Received the mutex lock");
+                        for binding in bindings.bindings() {
+                            echoinfo!("Binding = {binding}");
+                        }
+                        echoinfo!("Learning = {:?}", bindings.learning());
+                        continue;
+                    }
+                    _ => (),
+                }
+                let params = selected_parameters(&mut main_params, &mut zones, &selected);
+                match key {
+                    "key" | "k" => match lily::LilyKeySignature::try_from(value) {
+                        Ok(v) => {
+                            echoinfo!("Update key={:?}", v);
+                            params.set_key(v);
+                        }
+                        Err(e) => report_note_error(e),
+                    },
                     "accidentals" | "a" => {
                         params.set_accidentals(match value.try_into() {
                             Ok(v) => {
@@ -345,66 +1001,365 @@ fn main() {
                             },
                         });
                     }
-                    "language" => {
-                        params.set_language(match value.try_into() {
-                            Ok(lang) => {
-                                echoinfo!("Update language={:?}", lang);
-                                lang
+                    "output-mode" | "om" => {
+                        params.set_output_mode(match value.try_into() {
+                            Ok(mode) => {
+                                echoinfo!("Update output-mode={:?}", mode);
+                                mode
                             }
                             Err(e) => match e {
-                                lily::LilypondLanguageError::InvalidLanguageString(lang) => {
-                                    echoerr!("Invalid language provided: {lang}");
+                                lilypond_midi_input::OutputModeError::InvalidOutputModeString(
+                                    mode,
+                                ) => {
+                                    echoerr!("Invalid output-mode provided: {mode}");
                                     continue;
                                 }
                             },
                         });
                     }
-                    "octave-entry" => {
-                        match value.try_into() {
-                            Ok(oe) => {
-                                params.set_previous_absolute_note_reference(None);
-                                echoinfo!(
-                                    "Previous absolute note reference set to {:?}",
-                                    params.previous_absolute_note_reference()
-                                );
-                                echoinfo!("Update octave-entry={:?}", oe);
-                                params.set_octave_entry(oe);
-                            }
+                    "drum-channel" => {
+                        if let Some(channel) = parse_drum_channel(value) {
+                            params.set_drum_channel(channel);
+                            echoinfo!("Update drum-channel={:?}", params.drum_channel());
+                        }
+                    }
+                    "chord-name-preference" | "cnp" => {
+                        match params.set_chord_name_preferences_str(value) {
+                            Ok(_) => echoinfo!(
+                                "Update chord-name-preference={:?}",
+                                params.chord_name_preferences()
+                            ),
                             Err(e) => match e {
-                                lily::OctaveEntryError::InvalidOctaveEntryString(oe) => {
-                                    echoerr!("Invalid octave-entry provided: {oe}");
-                                    continue;
+                                lily::ChordNameError::InvalidChordNamePreferenceString(p) => {
+                                    echoerr!("Invalid chord-name-preference provided: {p}")
                                 }
                             },
-                        };
-                    }
-                    "octave-check-notes" => {
-                        match value {
-                            "true" => {
-                                params.set_octave_check_notes(true);
-                            }
-                            _ => {
-                                params.set_octave_check_notes(false);
-                            }
                         }
-                        echoinfo!(
-                            "Update octave-check-notes={:?}",
-                            params.octave_check_notes()
-                        );
                     }
-                    "octave-check-on-next-note" | "oconn" => {
-                        match value {
-                            "true" => {
-                                params.set_octave_check_on_next_note(true);
+                    "figures-output" => match value.parse::<OutputTarget>() {
+                        Ok(target) => {
+                            echoinfo!("Update figures-output={:?}", target);
+                            params.set_figures_output(target);
+                        }
+                        Err(OutputTargetError::InvalidOutputTargetString(t)) => {
+                            echoerr!("Invalid figures-output provided: {t}")
+                        }
+                    },
+                    "hand-split" => match params.set_hand_split_str(value) {
+                        Ok(_) => echoinfo!("Update hand-split={:?}", params.hand_split()),
+                        Err(e) => match e {
+                            lily::HandSplitError::InvalidHandSplitString(split) => {
+                                echoerr!("Invalid hand-split provided: {split}")
                             }
-                            _ => {
-                                params.set_octave_check_on_next_note(false);
+                        },
+                    },
+                    "right-hand-output" | "left-hand-output" => {
+                        match value.parse::<OutputTarget>() {
+                            Ok(target) => {
+                                echoinfo!("Update {}={:?}", key, target);
+                                match key {
+                                    "right-hand-output" => params.set_right_hand_output(target),
+                                    _ => params.set_left_hand_output(target),
+                                };
+                            }
+                            Err(OutputTargetError::InvalidOutputTargetString(t)) => {
+                                echoerr!("Invalid {key} provided: {t}")
                             }
                         }
-                        echoinfo!(
-                            "Update octave-check-on-next-note={:?}",
-                            params.octave_check_on_next_note()
-                        );
+                    }
+                    "keyswitches" => match value {
+                        "clear" => {
+                            params.clear_keyswitches();
+                            echoinfo!("Cleared all keyswitches");
+                        }
+                        _ => match parse_subkeys(&re_subkeyval, value) {
+                            Some(actions) => {
+                                if actions.is_empty() {
+                                    echoinfo!("No keyswitches were parsed/given");
+                                }
+                                for (note, action) in actions {
+                                    match params.add_keyswitch(note, &action) {
+                                        Ok(_) => {
+                                            echoinfo!("Update keyswitches={:?}:{:?}", note, action)
+                                        }
+                                        Err(lily::KeyswitchError::InvalidKeyswitchString(a)) => {
+                                            echoerr!("Invalid keyswitch action provided: {a}")
+                                        }
+                                    }
+                                }
+                            }
+                            None => echoerr!("One of the keys is not a number"),
+                        },
+                    },
+                    "modifiers" => match value {
+                        "clear" => {
+                            params.clear_modifiers();
+                            echoinfo!("Cleared all modifiers");
+                        }
+                        _ => {
+                            for cap in re_subkeyval.captures_iter(value) {
+                                let control = cap.name("key").expect("Valid named group").as_str();
+                                let suffix = cap.name("value").expect("Valid named group").as_str();
+                                match params.add_modifier(control, suffix) {
+                                    Ok(_) => {
+                                        echoinfo!("Update modifiers={:?}:{:?}", control, suffix)
+                                    }
+                                    Err(lily::ModifierError::InvalidModifierControlString(c)) => {
+                                        echoerr!("Invalid modifier control provided: {c}")
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "undo-output" => match value.parse::<OutputTarget>() {
+                        Ok(target) => {
+                            echoinfo!("Update undo-output={:?}", target);
+                            params.set_undo_output(target);
+                        }
+                        Err(OutputTargetError::InvalidOutputTargetString(t)) => {
+                            echoerr!("Invalid undo-output provided: {t}")
+                        }
+                    },
+                    "status-output" => match value {
+                        "none" => {
+                            echoinfo!("Update status-output=none");
+                            params.set_status_output(None);
+                        }
+                        _ => match value.parse::<OutputTarget>() {
+                            Ok(target) => {
+                                echoinfo!("Update status-output={:?}", target);
+                                params.set_status_output(Some(target));
+                                if *params.octave_shift() != 0 {
+                                    params.report_octave_shift();
+                                }
+                            }
+                            Err(OutputTargetError::InvalidOutputTargetString(t)) => {
+                                echoerr!("Invalid status-output provided: {t}")
+                            }
+                        },
+                    },
+                    "drum-map" => match value {
+                        "clear" => {
+                            params.clear_drum_names();
+                            echoinfo!("Cleared all drum names");
+                        }
+                        _ => match parse_subkeys(&re_subkeyval, value) {
+                            Some(names) => {
+                                if names.is_empty() {
+                                    echoinfo!("No drum names were parsed/given");
+                                }
+                                for (note, name) in names {
+                                    echoinfo!("Update drum-map={:?}:{:?}", note, name);
+                                    params.add_drum_name(note, name);
+                                }
+                            }
+                            None => echoerr!("One of the keys is not a number"),
+                        },
+                    },
+                    "language" => {
+                        params.set_language(match value.try_into() {
+                            Ok(lang) => {
+                                echoinfo!("Update language={:?}", lang);
+                                lang
+                            }
+                            Err(e) => match e {
+                                lily::LilypondLanguageError::InvalidLanguageString(lang) => {
+                                    echoerr!("Invalid language provided: {lang}");
+                                    continue;
+                                }
+                            },
+                        });
+                    }
+                    "octave-entry" => {
+                        match value.try_into() {
+                            Ok(oe) => {
+                                params.set_previous_absolute_note_reference(None);
+                                echoinfo!(
+                                    "Previous absolute note reference set to {:?}",
+                                    params.previous_absolute_note_reference()
+                                );
+                                echoinfo!("Update octave-entry={:?}", oe);
+                                params.set_octave_entry(oe);
+                            }
+                            Err(e) => match e {
+                                lily::OctaveEntryError::InvalidOctaveEntryString(oe) => {
+                                    echoerr!("Invalid octave-entry provided: {oe}");
+                                    continue;
+                                }
+                            },
+                        };
+                    }
+                    "fixed-reference" => match params.set_fixed_reference_lilypond_str(value) {
+                        Ok(_) => {
+                            echoinfo!("Update fixed-reference={:?}", params.fixed_reference())
+                        }
+                        Err(e) => report_note_error(e),
+                    },
+                    "transposition" | "tr" => match params.set_transposition_lilypond_str(value) {
+                        Ok(_) => {
+                            echoinfo!("Update transposition={:?}", params.transposition());
+                            if *params.transposition_command() {
+                                output!("\\transposition {}", params.transposition_lilypond_str());
+                            }
+                        }
+                        Err(e) => report_note_error(e),
+                    },
+                    "transposition-command" => {
+                        params.set_transposition_command(value == "true");
+                        echoinfo!(
+                            "Update transposition-command={:?}",
+                            params.transposition_command()
+                        );
+                    }
+                    "instrument" => match Instrument::try_from(value) {
+                        Ok(instrument) => {
+                            params.set_instrument(&instrument);
+                            echoinfo!("Update instrument={:?}", instrument);
+                            echoinfo!("Update range={:?}", params.range());
+                        }
+                        Err(e) => match e {
+                            lily::InstrumentError::InvalidInstrumentString(instrument) => {
+                                echoerr!("Invalid instrument provided: {instrument}")
+                            }
+                        },
+                    },
+                    "range" => match value {
+                        "clear" => {
+                            params.set_range(None);
+                            echoinfo!("Cleared the range");
+                        }
+                        _ => match params.set_range_lilypond_str(value) {
+                            Ok(_) => echoinfo!("Update range={:?}", params.range()),
+                            Err(e) => echoerr!("Invalid range provided: {:?}", e),
+                        },
+                    },
+                    "tuning" => match params.set_tuning_str(value) {
+                        Ok(_) => echoinfo!("Update tuning={:?}", params.strings()),
+                        Err(e) => match e {
+                            lily::TuningError::InvalidTuningString(tuning) => {
+                                echoerr!("Invalid tuning provided: {tuning}")
+                            }
+                        },
+                    },
+                    "clef" => {
+                        params.set_clef(match value.try_into() {
+                            Ok(clef) => {
+                                echoinfo!("Update clef={:?}", clef);
+                                clef
+                            }
+                            Err(e) => match e {
+                                lily::ClefError::InvalidClefString(clef) => {
+                                    echoerr!("Invalid clef provided: {clef}");
+                                    continue;
+                                }
+                            },
+                        });
+                    }
+                    "auto-clef" => {
+                        params.set_auto_clef(value == "true");
+                        echoinfo!("Update auto-clef={:?}", params.auto_clef());
+                    }
+                    "clefs" => match params.set_clefs_str(value) {
+                        Ok(_) => echoinfo!("Update clefs={:?}", params.clefs()),
+                        Err(e) => match e {
+                            lily::ClefError::InvalidClefString(clef) => {
+                                echoerr!("Invalid clef provided: {clef}")
+                            }
+                        },
+                    },
+                    "clef-ledger-lines" => match value.parse::<u8>() {
+                        Ok(lines) => {
+                            params.set_clef_ledger_lines(lines);
+                            echoinfo!("Update clef-ledger-lines={:?}", lines);
+                        }
+                        Err(_) => {
+                            echoerr!("Number of ledger lines is not an unsigned number: {value}")
+                        }
+                    },
+                    "clef-notes" => match value.parse::<u8>() {
+                        Ok(notes) => {
+                            params.set_clef_notes(notes);
+                            echoinfo!("Update clef-notes={:?}", notes);
+                        }
+                        Err(_) => echoerr!("Number of notes is not an unsigned number: {value}"),
+                    },
+                    "ottava" => {
+                        if let Some(ottava) = parse_ottava(value) {
+                            params.set_ottava_ledger_lines(ottava);
+                            echoinfo!("Update ottava={:?}", params.ottava_ledger_lines());
+                        }
+                    }
+                    "octave-check-notes" => {
+                        match value {
+                            "true" => {
+                                params.set_octave_check_notes(true);
+                            }
+                            _ => {
+                                params.set_octave_check_notes(false);
+                            }
+                        }
+                        echoinfo!(
+                            "Update octave-check-notes={:?}",
+                            params.octave_check_notes()
+                        );
+                    }
+                    "octave-check-on-next-note" | "oconn" => {
+                        match value {
+                            "true" => {
+                                params.set_octave_check_on_next_note(true);
+                            }
+                            _ => {
+                                params.set_octave_check_on_next_note(false);
+                            }
+                        }
+                        echoinfo!(
+                            "Update octave-check-on-next-note={:?}",
+                            params.octave_check_on_next_note()
+                        );
+                    }
+                    "octave-check-every" => match value.parse::<u8>() {
+                        Ok(every) => {
+                            params.set_octave_check_every(every);
+                            echoinfo!("Update octave-check-every={:?}", every);
+                        }
+                        Err(_) => echoerr!("Number of notes is not an unsigned number: {value}"),
+                    },
+                    "octave-check-leaps" => {
+                        params.set_octave_check_leaps(value == "true");
+                        echoinfo!(
+                            "Update octave-check-leaps={:?}",
+                            params.octave_check_leaps()
+                        );
+                    }
+                    "octave-check-chords" => {
+                        params.set_octave_check_chords(value == "true");
+                        echoinfo!(
+                            "Update octave-check-chords={:?}",
+                            params.octave_check_chords()
+                        );
+                    }
+                    "octave-check-after-q" => {
+                        params.set_octave_check_after_q(value == "true");
+                        echoinfo!(
+                            "Update octave-check-after-q={:?}",
+                            params.octave_check_after_q()
+                        );
+                    }
+                    "octave-check-style" => {
+                        params.set_octave_check_style(match value.try_into() {
+                            Ok(style) => {
+                                echoinfo!("Update octave-check-style={:?}", style);
+                                style
+                            }
+                            Err(e) => match e {
+                                lily::OctaveCheckStyleError::InvalidOctaveCheckStyleString(
+                                    style,
+                                ) => {
+                                    echoerr!("Invalid octave-check-style provided: {style}");
+                                    continue;
+                                }
+                            },
+                        });
                     }
                     "alterations" | "alt" => match value {
                         "clear" => {
@@ -450,36 +1405,114 @@ fn main() {
                             None => echoerr!("One of the keys is not a number"),
                         },
                     },
-                    "previous-chord" | "pc" => {
-                        match value {
-                            "clear" => {
-                                params.set_previous_chord(Some(BTreeSet::new()));
+                    "alt-preset-define" | "altpd" => {
+                        define_alteration_preset(params, &re_subkeyval, value);
+                    }
+                    "alt-preset-file" => {
+                        load_alteration_presets(params, &re_subkeyval, value);
+                    }
+                    "alt-preset" | "altp" => {
+                        let name = match value {
+                            "clear" => None,
+                            _ => Some(String::from(value)),
+                        };
+                        match params.set_alteration_preset(name) {
+                            Ok(_) => {
+                                echoinfo!("Update alt-preset={:?}", params.alteration_preset())
                             }
-                            _ => {
-                                match params.set_previous_chord_lilypond_str(
-                                    value.split(':').map(String::from).collect(),
-                                ) {
-                                    Ok(_) => {
-                                        echoinfo!(
-                                            "Previous chord set to {:?}",
-                                            params.previous_chord().unwrap()
-                                        )
-                                    }
-                                    Err(e) => match e {
-                                        lily::LilypondNoteError::OutsideOctave(_) => {
-                                            panic!("This error should not occur here.")
+                            Err(e) => echoerr!("Invalid alteration preset was given: {:?}", e),
+                        }
+                    }
+                    "spelling" | "sp" => match value {
+                        "clear" => {
+                            params.clear_spellings();
+                            echoinfo!("Cleared all spellings");
+                        }
+                        _ => match parse_subkeys(&re_subkeyval, value) {
+                            Some(spellings) => {
+                                if spellings.is_empty() {
+                                    echoinfo!("No spellings were parsed/given");
+                                }
+                                for (note, value) in spellings {
+                                    match params.add_spelling(note, &value) {
+                                        Ok(_) => {
+                                            echoinfo!("Update spelling={:?}:{:?}", note, value);
                                         }
-                                        lily::LilypondNoteError::InvalidKeyString(_) => {
-                                            panic!("This error should not occur here.")
+                                        Err(e) => echoerr!("Invalid spelling was given: {:?}", e),
+                                    };
+                                }
+                            }
+                            None => echoerr!("One of the keys is not a number"),
+                        },
+                    },
+                    "global-spelling" | "gsp" => match value {
+                        "clear" => {
+                            params.clear_global_spellings();
+                            echoinfo!("Cleared all global spellings");
+                        }
+                        _ => match parse_subkeys(&re_subkeyval, value) {
+                            Some(spellings) => {
+                                if spellings.is_empty() {
+                                    echoinfo!("No global spellings were parsed/given");
+                                }
+                                for (note, value) in spellings {
+                                    match params.add_global_spelling(note, &value) {
+                                        Ok(_) => {
+                                            echoinfo!(
+                                                "Update global-spelling={:?}:{:?}",
+                                                note,
+                                                value
+                                            );
                                         }
-                                        lily::LilypondNoteError::InvalidNoteString(note) => {
-                                            echoerr!("Invalid/Unrecognized LilyPond note provided: {note}")
+                                        Err(e) => {
+                                            echoerr!("Invalid global spelling was given: {:?}", e)
                                         }
-                                    },
+                                    };
                                 }
                             }
-                        }
+                            None => echoerr!("One of the keys is not a number"),
+                        },
+                    },
+                    "pitch-bend-resolution" | "pbres" => {
+                        params.set_pitch_bend_resolution(match value.try_into() {
+                            Ok(res) => {
+                                echoinfo!("Update pitch-bend-resolution={:?}", res);
+                                res
+                            }
+                            Err(e) => match e {
+                                lily::PitchBendError::InvalidPitchBendResolutionString(res) => {
+                                    echoerr!("Invalid pitch-bend-resolution provided: {res}");
+                                    continue;
+                                }
+                            },
+                        });
                     }
+                    "pitch-bend-range" | "pbr" => match value.parse::<u8>() {
+                        Ok(range) => {
+                            params.set_pitch_bend_range(range);
+                            echoinfo!("Update pitch-bend-range={:?}", range);
+                        }
+                        Err(_) => echoerr!("Pitch bend range is not an unsigned number: {value}"),
+                    },
+                    "octave-shift" | "os" => shift_octaves(params, value),
+                    "previous-chord" | "pc" => match value {
+                        "clear" => {
+                            params.set_previous_chord(Some(BTreeSet::new()));
+                        }
+                        _ => {
+                            match params.set_previous_chord_lilypond_str(
+                                value.split(':').map(String::from).collect(),
+                            ) {
+                                Ok(_) => {
+                                    echoinfo!(
+                                        "Previous chord set to {:?}",
+                                        params.previous_chord().unwrap()
+                                    )
+                                }
+                                Err(e) => report_note_error(e),
+                            }
+                        }
+                    },
                     "previous-absolute-note-reference" | "panr" => match value {
                         "clear" => {
                             params.set_previous_absolute_note_reference(None);
@@ -493,17 +1526,7 @@ fn main() {
                                     params.previous_absolute_note_reference().unwrap()
                                 )
                             }
-                            Err(e) => match e {
-                                lily::LilypondNoteError::OutsideOctave(_) => {
-                                    panic!("This error should not occur here.")
-                                }
-                                lily::LilypondNoteError::InvalidKeyString(_) => {
-                                    panic!("This error should not occur here.")
-                                }
-                                lily::LilypondNoteError::InvalidNoteString(note) => {
-                                    echoerr!("Invalid/Unrecognized LilyPond note provided: {note}")
-                                }
-                            },
+                            Err(e) => report_note_error(e),
                         },
                     },
                     "list" => match value {
@@ -512,8 +1535,59 @@ fn main() {
                             echoinfo!("Accidentals = {:?}", params.accidentals())
                         }
                         "mode" | "m" => echoinfo!("Mode = {:?}", params.mode()),
+                        "output-mode" | "om" => {
+                            echoinfo!("Output mode = {:?}", params.output_mode())
+                        }
+                        "drum-channel" => echoinfo!("Drum channel = {:?}", params.drum_channel()),
+                        "drum-map" => echoinfo!("Drum map = {:?}", params.drum_names()),
+                        "keyswitches" => echoinfo!("Keyswitches = {:?}", params.keyswitches()),
+                        "modifiers" => echoinfo!("Modifiers = {:?}", params.modifiers()),
+                        "undo-output" => {
+                            echoinfo!("Undo output = {:?}", params.undo_output())
+                        }
+                        "status-output" => {
+                            echoinfo!("Status output = {:?}", params.status_output())
+                        }
+                        "chord-name-preference" | "cnp" => {
+                            echoinfo!(
+                                "Chord name preference = {:?}",
+                                params.chord_name_preferences()
+                            )
+                        }
+                        "figures-output" => {
+                            echoinfo!("Figures output = {:?}", params.figures_output())
+                        }
+                        "hand-split" => echoinfo!("Hand split = {:?}", params.hand_split()),
+                        "right-hand-output" => {
+                            echoinfo!("Right hand output = {:?}", params.right_hand_output())
+                        }
+                        "left-hand-output" => {
+                            echoinfo!("Left hand output = {:?}", params.left_hand_output())
+                        }
                         "language" => echoinfo!("Language = {:?}", params.language()),
                         "octave-entry" => echoinfo!("Octave entry = {:?}", params.octave_entry()),
+                        "fixed-reference" => {
+                            echoinfo!("Fixed reference = {:?}", params.fixed_reference())
+                        }
+                        "transposition" | "tr" => {
+                            echoinfo!("Transposition = {:?}", params.transposition())
+                        }
+                        "transposition-command" => {
+                            echoinfo!(
+                                "Transposition command = {:?}",
+                                params.transposition_command()
+                            )
+                        }
+                        "range" => echoinfo!("Range = {:?}", params.range()),
+                        "tuning" => echoinfo!("Tuning = {:?}", params.strings()),
+                        "clef" => echoinfo!("Clef = {:?}", params.clef()),
+                        "auto-clef" => echoinfo!("Auto clef = {:?}", params.auto_clef()),
+                        "clefs" => echoinfo!("Clefs = {:?}", params.clefs()),
+                        "clef-ledger-lines" => {
+                            echoinfo!("Clef ledger lines = {:?}", params.clef_ledger_lines())
+                        }
+                        "clef-notes" => echoinfo!("Clef notes = {:?}", params.clef_notes()),
+                        "ottava" => echoinfo!("Ottava = {:?}", params.ottava_ledger_lines()),
                         "octave-check-notes" => {
                             echoinfo!("Octave check notes = {:?}", params.octave_check_notes())
                         }
@@ -523,12 +1597,49 @@ fn main() {
                                 params.octave_check_on_next_note()
                             )
                         }
+                        "octave-check-every" => {
+                            echoinfo!("Octave check every = {:?}", params.octave_check_every())
+                        }
+                        "octave-check-leaps" => {
+                            echoinfo!("Octave check leaps = {:?}", params.octave_check_leaps())
+                        }
+                        "octave-check-chords" => {
+                            echoinfo!("Octave check chords = {:?}", params.octave_check_chords())
+                        }
+                        "octave-check-after-q" => {
+                            echoinfo!("Octave check after q = {:?}", params.octave_check_after_q())
+                        }
+                        "octave-check-style" => {
+                            echoinfo!("Octave check style = {:?}", params.octave_check_style())
+                        }
                         "alterations" | "alt" => {
                             echoinfo!("Alterations = {:?}", params.alterations())
                         }
                         "global-alterations" | "galt" => {
                             echoinfo!("Global alterations = {:?}", params.global_alterations())
                         }
+                        "alt-preset" | "altp" => {
+                            echoinfo!("Alteration preset = {:?}", params.alteration_preset())
+                        }
+                        "alt-presets" => {
+                            echoinfo!("Alteration presets = {:?}", params.alteration_presets())
+                        }
+                        "spelling" | "sp" => echoinfo!("Spellings = {:?}", params.spellings()),
+                        "global-spelling" | "gsp" => {
+                            echoinfo!("Global spellings = {:?}", params.global_spellings())
+                        }
+                        "pitch-bend-resolution" | "pbres" => {
+                            echoinfo!(
+                                "Pitch bend resolution = {:?}",
+                                params.pitch_bend_resolution()
+                            )
+                        }
+                        "pitch-bend-range" | "pbr" => {
+                            echoinfo!("Pitch bend range = {:?}", params.pitch_bend_range())
+                        }
+                        "octave-shift" | "os" => {
+                            echoinfo!("Octave shift = {:?}", params.octave_shift())
+                        }
                         "previous-chord" | "pc" => {
                             echoinfo!("Previous chord = {:?}", params.previous_chord())
                         }
@@ -542,15 +1653,59 @@ fn main() {
                             echoinfo!("Key = {:?}", params.key());
                             echoinfo!("Accidentals = {:?}", params.accidentals());
                             echoinfo!("Mode = {:?}", params.mode());
+                            echoinfo!("Output mode = {:?}", params.output_mode());
+                            echoinfo!("Drum channel = {:?}", params.drum_channel());
+                            echoinfo!("Drum map = {:?}", params.drum_names());
+                            echoinfo!("Keyswitches = {:?}", params.keyswitches());
+                            echoinfo!("Modifiers = {:?}", params.modifiers());
+                            echoinfo!("Undo output = {:?}", params.undo_output());
+                            echoinfo!("Status output = {:?}", params.status_output());
+                            echoinfo!(
+                                "Chord name preference = {:?}",
+                                params.chord_name_preferences()
+                            );
+                            echoinfo!("Figures output = {:?}", params.figures_output());
+                            echoinfo!("Hand split = {:?}", params.hand_split());
+                            echoinfo!("Right hand output = {:?}", params.right_hand_output());
+                            echoinfo!("Left hand output = {:?}", params.left_hand_output());
                             echoinfo!("Language = {:?}", params.language());
                             echoinfo!("Octave entry = {:?}", params.octave_entry());
+                            echoinfo!("Fixed reference = {:?}", params.fixed_reference());
+                            echoinfo!("Transposition = {:?}", params.transposition());
+                            echoinfo!(
+                                "Transposition command = {:?}",
+                                params.transposition_command()
+                            );
+                            echoinfo!("Range = {:?}", params.range());
+                            echoinfo!("Tuning = {:?}", params.strings());
+                            echoinfo!("Clef = {:?}", params.clef());
+                            echoinfo!("Auto clef = {:?}", params.auto_clef());
+                            echoinfo!("Clefs = {:?}", params.clefs());
+                            echoinfo!("Clef ledger lines = {:?}", params.clef_ledger_lines());
+                            echoinfo!("Clef notes = {:?}", params.clef_notes());
+                            echoinfo!("Ottava = {:?}", params.ottava_ledger_lines());
                             echoinfo!("Octave check notes = {:?}", params.octave_check_notes());
                             echoinfo!(
                                 "Octave check on next note = {:?}",
                                 params.octave_check_on_next_note()
                             );
+                            echoinfo!("Octave check every = {:?}", params.octave_check_every());
+                            echoinfo!("Octave check leaps = {:?}", params.octave_check_leaps());
+                            echoinfo!("Octave check chords = {:?}", params.octave_check_chords());
+                            echoinfo!("Octave check after q = {:?}", params.octave_check_after_q());
+                            echoinfo!("Octave check style = {:?}", params.octave_check_style());
                             echoinfo!("Alterations = {:?}", params.alterations());
                             echoinfo!("Global alterations = {:?}", params.global_alterations());
+                            echoinfo!("Alteration preset = {:?}", params.alteration_preset());
+                            echoinfo!("Alteration presets = {:?}", params.alteration_presets());
+                            echoinfo!("Spellings = {:?}", params.spellings());
+                            echoinfo!("Global spellings = {:?}", params.global_spellings());
+                            echoinfo!(
+                                "Pitch bend resolution = {:?}",
+                                params.pitch_bend_resolution()
+                            );
+                            echoinfo!("Pitch bend range = {:?}", params.pitch_bend_range());
+                            echoinfo!("Octave shift = {:?}", params.octave_shift());
                             echoinfo!("Previous chord = {:?}", params.previous_chord());
                             echoinfo!(
                                 "Previous absolute note reference = {:?}",
@@ -571,37 +1726,1008 @@ fn main() {
     };
 }
 
+/// The notes by name instead of as LilyPond notes
+///
+/// Like for LilyPond notes, the notes are named one by one, or as a chord (or `q` when it is
+/// repeated) once all of them were released when using chords. Notes and chords without a name
+/// are ignored.
+fn name_notes(
+    notes: &mut BTreeSet<MidiNote>,
+    pressed: &BTreeSet<MidiNote>,
+    use_chords: bool,
+    last_chord: &mut Option<BTreeSet<MidiNote>>,
+    name: impl Fn(&BTreeSet<MidiNote>) -> Option<String>,
+) -> Option<String> {
+    let chord = take_notes(notes, pressed, use_chords)?;
+    if chord.len() > 1 && last_chord.as_ref() == Some(&chord) {
+        return Some("q".into());
+    }
+    match name(&chord) {
+        Some(name) => {
+            if chord.len() > 1 {
+                *last_chord = Some(chord);
+            }
+            Some(name)
+        }
+        None => {
+            echoerr!("Ignoring notes which cannot be named: {:?}", chord);
+            None
+        }
+    }
+}
+
+/// Take the notes to be output next: a single note, or all notes forming a chord once they were
+/// released when using chords
+///
+/// Returns `None` if there is nothing to output yet.
+fn take_notes(
+    notes: &mut BTreeSet<MidiNote>,
+    pressed: &BTreeSet<MidiNote>,
+    use_chords: bool,
+) -> Option<BTreeSet<MidiNote>> {
+    match use_chords {
+        false => notes.pop_first().map(|note| BTreeSet::from([note])),
+        true if pressed.is_empty() && !notes.is_empty() => Some(std::mem::take(notes)),
+        true => None,
+    }
+}
+
+/// The notes being played on the keyboard (or in one of its zones), and their output state
+#[derive(Default)]
+struct Engine {
+    /// notes to be put into a chord
+    notes: BTreeSet<MidiNote>,
+    /// notes being pressed to know when everything was released
+    pressed: BTreeSet<MidiNote>,
+    /// how long notes are held to separate them into voices
+    voices: lily::VoiceSeparation,
+    /// output state of each staff (only the first one unless hands are split)
+    staves: [Staff; 2],
+    /// written notes for the notes being played on a transposing instrument
+    written: HashMap<MidiNote, MidiNote>,
+    /// quarter tones by which notes were bent when they were pressed
+    bends: HashMap<MidiNote, i16>,
+    /// previous outputs which can be taken back, the latest last
+    history: VecDeque<Undo>,
+    /// notes the pressed keys were shifted to, such that they are released with the octave
+    /// shift they were pressed with
+    shifted: HashMap<MidiNote, MidiNote>,
+}
+
+/// Press or release a controller for all parameters which use it as a modifier control (see
+/// [lily::LilyParameters::hold_modifier]), returning whether any of them does
+fn hold_modifier(
+    params: &mut lily::LilyParameters,
+    zones: &mut [Zone],
+    controller: u8,
+    held: bool,
+) -> bool {
+    let control = lily::ModifierControl::Controller(controller);
+    let mut used = params.hold_modifier(control, held);
+    for zone in zones {
+        used |= zone.parameters_mut().hold_modifier(control, held);
+    }
+    used
+}
+
+/// Number of previous outputs which can be taken back
+const UNDO_LIMIT: usize = 100;
+
+/// An output which can be taken back, with the state from before it to return to
+struct Undo {
+    /// the output which is taken back
+    line: String,
+    /// the hand the output was written for
+    hand: Hand,
+    reference: Option<MidiNote>,
+    reference_spelling: Option<lily::Note>,
+    octave_check_on_next_note: bool,
+    notes_since_octave_check: u8,
+    clef: lily::Clef,
+    hand_position: Option<u8>,
+    pending_modifiers: BTreeSet<lily::ModifierControl>,
+    /// previous chord, recent notes and ottava of the staff
+    staff: Staff,
+}
+
+impl Undo {
+    /// The state before the next output on the given staff
+    fn new(params: &mut lily::LilyParameters, staff: &Staff) -> Self {
+        Undo {
+            line: String::new(),
+            hand: *params.hand(),
+            reference: params.previous_absolute_note_reference().cloned(),
+            reference_spelling: *params.previous_absolute_note_reference_spelling(),
+            octave_check_on_next_note: *params.octave_check_on_next_note(),
+            notes_since_octave_check: *params.notes_since_octave_check(),
+            clef: params.clef().clone(),
+            hand_position: *params.hand_position(),
+            pending_modifiers: params.pending_modifiers().clone(),
+            staff: staff.clone(),
+        }
+    }
+}
+
+/// Remember the given output, which was made from the given state, such that it can be undone
+fn remember(history: &mut VecDeque<Undo>, undo: Undo, line: &str) {
+    history.push_back(Undo {
+        line: line.into(),
+        ..undo
+    });
+    if history.len() > UNDO_LIMIT {
+        history.pop_front();
+    }
+}
+
+/// Perform the action of a command key
+///
+/// An undo restores the state from before the latest output (the reference for relative octave
+/// entry, octave checks, the clef and ottava, the hand position and the pending modifiers), and
+/// writes the output which was taken back to the undo output, such that editors can remove it.
+fn run_keyswitch(
+    action: lily::KeyswitchAction,
+    params: &mut lily::LilyParameters,
+    staves: &mut [Staff; 2],
+    history: &mut VecDeque<Undo>,
+    target: Option<&OutputTarget>,
+) {
+    let line = match action {
+        lily::KeyswitchAction::Rest => "r",
+        lily::KeyswitchAction::Skip => "s",
+        lily::KeyswitchAction::FullBarRest => "R",
+        lily::KeyswitchAction::BarCheck => "|",
+        lily::KeyswitchAction::Undo => {
+            match history.pop_back() {
+                Some(undo) => {
+                    params.switch_hand(undo.hand);
+                    params
+                        .set_previous_absolute_note_reference(undo.reference)
+                        .set_previous_absolute_note_reference_spelling(undo.reference_spelling);
+                    params
+                        .set_octave_check_on_next_note(undo.octave_check_on_next_note)
+                        .set_notes_since_octave_check(undo.notes_since_octave_check)
+                        .set_clef(undo.clef)
+                        .set_hand_position(undo.hand_position)
+                        .set_pending_modifiers(undo.pending_modifiers);
+                    staves[undo.hand as usize] = undo.staff;
+                    params.undo_output().write(&undo.line);
+                }
+                None => echoerr!("Nothing to undo"),
+            }
+            return;
+        }
+        lily::KeyswitchAction::ToggleChords => {
+            let mode = match params.mode() {
+                InputMode::Single => InputMode::Chord,
+                InputMode::Chord => InputMode::Single,
+                InputMode::PedalChord => InputMode::PedalSingle,
+                InputMode::PedalSingle => InputMode::PedalChord,
+                // voices already group the notes played together
+                InputMode::Voices => return,
+            };
+            echoinfo!("Update mode={:?}", mode);
+            params.set_mode(mode);
+            return;
+        }
+        lily::KeyswitchAction::OctaveUp => {
+            shift_octaves(params, "up");
+            return;
+        }
+        lily::KeyswitchAction::OctaveDown => {
+            shift_octaves(params, "down");
+            return;
+        }
+        lily::KeyswitchAction::CycleAccidentals => {
+            let accidentals = match params.accidentals() {
+                LilyAccidental::Sharps => LilyAccidental::Flats,
+                LilyAccidental::Flats => LilyAccidental::Sharps,
+            };
+            echoinfo!("Update accidentals={:?}", accidentals);
+            params.set_accidentals(accidentals);
+            return;
+        }
+    };
+    let hand = *params.hand() as usize;
+    let undo = Undo::new(params, &staves[hand]);
+    remember(history, undo, line);
+    emit(target, line);
+}
+
+/// Enter a note being pressed or released, and output the notes which are complete
+///
+/// The time of the message (in milliseconds) is used to separate voices. Lines are written to
+/// the given target, or to stdout if there is none.
+fn handle_note(
+    params: &mut lily::LilyParameters,
+    engine: &mut Engine,
+    pedals: &BTreeSet<MidiNote>,
+    pitch_bend: i16,
+    message: midi::MidiMessageType,
+    time: u32,
+    target: Option<&OutputTarget>,
+) {
+    let Engine {
+        notes,
+        pressed,
+        voices,
+        staves,
+        written,
+        bends,
+        history,
+        shifted,
+    } = engine;
+    if let midi::MidiMessageType::NoteOn { note, .. }
+    | midi::MidiMessageType::NoteOff { note, .. } = message
+    {
+        // command keys never produce notes, and act when they are pressed
+        if let Some(action) = params.keyswitch(note).cloned() {
+            if let midi::MidiMessageType::NoteOn { .. } = message {
+                run_keyswitch(action, params, staves, history, target);
+            }
+            return;
+        }
+        // modifier keys never produce notes either, and apply while they are held
+        let held = matches!(message, midi::MidiMessageType::NoteOn { .. });
+        if params.hold_modifier(lily::ModifierControl::Key(note), held) {
+            return;
+        }
+    }
+    // drum names belong to the keys being played, hence drums are never shifted
+    let message = match message {
+        midi::MidiMessageType::NoteOn {
+            note,
+            velocity,
+            channel,
+        } if *params.output_mode() != OutputMode::Drums => match params.shift_octave(note) {
+            Some(shifted_note) => {
+                shifted.insert(note, shifted_note);
+                midi::MidiMessageType::NoteOn {
+                    note: shifted_note,
+                    velocity,
+                    channel,
+                }
+            }
+            None => {
+                echoerr!("Ignoring note which is shifted outside of the MIDI range: {note}");
+                return;
+            }
+        },
+        midi::MidiMessageType::NoteOff {
+            note,
+            velocity,
+            channel,
+        } => midi::MidiMessageType::NoteOff {
+            note: shifted.remove(&note).unwrap_or(note),
+            velocity,
+            channel,
+        },
+        message => message,
+    };
+    let use_chords: bool = match params.mode() {
+        InputMode::Single => false,
+        InputMode::Chord => true,
+        InputMode::PedalChord => !pedals.is_empty(),
+        InputMode::PedalSingle => pedals.is_empty(),
+        InputMode::Voices => true,
+    };
+    let use_voices =
+        *params.mode() == InputMode::Voices && *params.output_mode() == OutputMode::Notes;
+    if let Some(prev_chord) = params.take_previous_chord() {
+        let last_chord = &mut staves[*params.hand() as usize].last_chord;
+        match prev_chord.is_empty() {
+            true => *last_chord = None,
+            false => *last_chord = Some(prev_chord),
+        }
+    }
+    match message {
+        midi::MidiMessageType::NoteOn { note, channel, .. } => {
+            if !params.accepts_channel(channel) {
+                return;
+            }
+            let written_note = match params.output_mode() {
+                OutputMode::Notes | OutputMode::ChordNames | OutputMode::FiguredBass => {
+                    if !params.in_range(note) {
+                        echoerr!("Note is outside of the instrument's range: {note}");
+                    }
+                    // notes are played at concert pitch, but written transposed
+                    let Some(written_note) = params.transpose(note) else {
+                        echoerr!("Ignoring note which cannot be written transposed: {note}");
+                        return;
+                    };
+                    written_note
+                }
+                OutputMode::Drums => {
+                    if params.drum_name(note).is_none() {
+                        echoerr!("Ignoring note without a drum name: {note}");
+                        return;
+                    }
+                    note
+                }
+            };
+            written.insert(note, written_note);
+            let note = written_note;
+            pressed.insert(note);
+            notes.insert(note);
+            if use_voices {
+                voices.note_on(note, time);
+            }
+            match params
+                .pitch_bend_resolution()
+                .quarter_tones(pitch_bend, *params.pitch_bend_range())
+            {
+                0 => bends.remove(&note),
+                quarter_tones => bends.insert(note, quarter_tones),
+            };
+        }
+        midi::MidiMessageType::NoteOff { note, channel, .. } => {
+            if !params.accepts_channel(channel) {
+                return;
+            }
+            if let Some(note) = written.remove(&note) {
+                pressed.remove(&note);
+                voices.note_off(note, time);
+            }
+        }
+        _ => unreachable!("Only notes are handled"),
+    }
+    match params.output_mode() {
+        OutputMode::Notes => (),
+        OutputMode::Drums => {
+            let undo = Undo::new(params, &staves[0]);
+            let line = name_notes(
+                notes,
+                pressed,
+                use_chords,
+                &mut staves[0].last_chord,
+                |notes| {
+                    let names: Vec<&str> = notes
+                        .iter()
+                        .map(|note| {
+                            params
+                                .drum_name(*note)
+                                .expect("Only notes with a drum name are kept")
+                        })
+                        .collect();
+                    match names.len() {
+                        1 => Some(names[0].into()),
+                        _ => Some(format!("<{}>", names.join(" "))),
+                    }
+                },
+            );
+            if let Some(line) = line {
+                remember(history, undo, &line);
+                emit(target, &line);
+            }
+            return;
+        }
+        OutputMode::ChordNames => {
+            let undo = Undo::new(params, &staves[0]);
+            let line = name_notes(
+                notes,
+                pressed,
+                use_chords,
+                &mut staves[0].last_chord,
+                |notes| params.chord_name(notes),
+            );
+            if let Some(line) = line {
+                remember(history, undo, &line);
+                emit(target, &line);
+            }
+            return;
+        }
+        OutputMode::FiguredBass => {
+            let Some(chord) = take_notes(notes, pressed, use_chords) else {
+                return;
+            };
+            let bass = *chord.first().expect("A note was pressed");
+            let figures = lily::figures(&chord, params);
+            for note in chord.iter().skip(1) {
+                bends.remove(note);
+            }
+            let bend = bends.remove(&bass);
+            let staff = &mut staves[*params.hand() as usize];
+            let undo = Undo::new(params, staff);
+            let line = note_string(params, bass, bend, staff);
+            remember(history, undo, &line);
+            emit(target, &line);
+            params.figures_output().write(&figures);
+            return;
+        }
+    }
+    if use_voices {
+        if voices.is_finished() {
+            notes.clear();
+            let staff = &mut staves[*params.hand() as usize];
+            let voices = voices.take_voices();
+            let undo = Undo::new(params, staff);
+            let line = voices_string(params, voices, bends, staff);
+            remember(history, undo, &line);
+            emit(target, &line);
+        }
+        return;
+    }
+    let Some(chord) = take_notes(notes, pressed, use_chords) else {
+        return;
+    };
+    let split = params.hand_split().is_some();
+    let (right, left) = params.split_hands(&chord);
+    for (hand, notes) in [(Hand::Right, right), (Hand::Left, left)] {
+        if notes.is_empty() {
+            continue;
+        }
+        params.switch_hand(hand);
+        let staff = &mut staves[hand as usize];
+        let undo = Undo::new(params, staff);
+        let line = notes_string(params, &notes, bends, staff);
+        remember(history, undo, &line);
+        match split {
+            true => params.hand_output(hand).write(&line),
+            false => emit(target, &line),
+        }
+    }
+}
+
+/// The parameters of the selected zone, or the parameters for notes outside of any zone if no
+/// zone is selected
+fn selected_parameters<'a>(
+    parameters: &'a mut lily::LilyParameters,
+    zones: &'a mut [Zone],
+    selected: &Option<String>,
+) -> &'a mut lily::LilyParameters {
+    match selected
+        .as_ref()
+        .and_then(|name| zones.iter_mut().find(|zone| zone.name() == name))
+    {
+        Some(zone) => zone.parameters_mut(),
+        None => parameters,
+    }
+}
+
+/// Write a line of output to the given target, or to stdout if there is none
+fn emit(target: Option<&OutputTarget>, line: &str) {
+    match target {
+        Some(target) => target.write(line),
+        None => output!("{line}"),
+    }
+}
+
+/// Output state which is kept for each staff
+#[derive(Clone, Default)]
+struct Staff {
+    /// last chord inserted (to insert a 'q' on repetition)
+    last_chord: Option<BTreeSet<MidiNote>>,
+    /// staff steps spanned by recent notes for automatic clef switching
+    recent: VecDeque<(i16, i16)>,
+    /// ottava in effect for the generated notes
+    ottava: i8,
+    /// reference pitch (and its spelling) of each voice for relative octave entry, from the
+    /// highest voice to the lowest
+    voice_references: Vec<(MidiNote, Option<lily::Note>)>,
+}
+
+/// The given notes as a single LilyPond note or a chord on the given staff
+fn notes_string(
+    parameters: &mut lily::LilyParameters,
+    notes: &BTreeSet<MidiNote>,
+    bends: &mut HashMap<MidiNote, i16>,
+    staff: &mut Staff,
+) -> String {
+    match notes.len() {
+        1 => {
+            let note = *notes.first().expect("A note was pressed");
+            let bend = bends.remove(&note);
+            note_string(parameters, note, bend, staff)
+        }
+        _ => chord_string(parameters, notes, bends, staff),
+    }
+}
+
+/// A LilyPond chord of the given notes, or `q` if it repeats the previous chord on the staff
+///
+/// The first note of the chord becomes the reference for relative octave entry.
+fn chord_string(
+    parameters: &mut lily::LilyParameters,
+    notes: &BTreeSet<MidiNote>,
+    bends: &mut HashMap<MidiNote, i16>,
+    staff: &mut Staff,
+) -> String {
+    parameters.apply_octave_check_policy(*notes.first().expect("At least one note is given"), true);
+    let previous_panr = parameters.previous_absolute_note_reference().cloned();
+    let previous_panr_spelling = *parameters.previous_absolute_note_reference_spelling();
+    let previous_oconn = *parameters.octave_check_on_next_note();
+    // Spell all notes consistently with respect to the chord's root
+    let spelling = lily::ChordAnalysis::new(notes)
+        .and_then(|chord| chord.spell(parameters))
+        .unwrap_or_default();
+    let strings = string_numbers(
+        parameters,
+        &notes.iter().copied().collect::<Vec<MidiNote>>(),
+    );
+    let modifiers = parameters.take_modifiers();
+    let note_modifiers = modifiers.without_post_events();
+    let bent = notes.iter().any(|note| bends.contains_key(note));
+    let mut first_reference = None;
+    let mut steps = Vec::new();
+    let chord: String = notes
+        .iter()
+        .zip(strings)
+        .map(|(note, string)| {
+            let lily_note = match bends.remove(note) {
+                Some(quarter_tones) => bent_lily_note(*note, Some(quarter_tones), parameters),
+                None => lily::LilyNote::new_spelled(*note, spelling.get(&(note % 12)), parameters),
+            }
+            // \octaveCheck cannot be used within a chord
+            .with_inline_octave_check()
+            .with_string(string)
+            .with_modifiers(&note_modifiers);
+            let (reference, note_spelling) = lily_note.reference();
+            steps.push(lily_note.staff_step(parameters));
+            let lily_note = lily_note.to_string();
+            first_reference.get_or_insert((reference, note_spelling));
+            // Need to calculate relative octave among notes in chord
+            parameters
+                .set_previous_absolute_note_reference(Some(reference))
+                .set_previous_absolute_note_reference_spelling(note_spelling);
+            parameters.set_octave_check_on_next_note(false);
+            lily_note
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let prefix = staff_prefix(
+        parameters,
+        (
+            *steps.iter().min().expect("At least one note is given"),
+            *steps.iter().max().expect("At least one note is given"),
+        ),
+        &mut staff.recent,
+        &mut staff.ottava,
+    );
+    match !bent && staff.last_chord.as_ref() == Some(notes) {
+        true => {
+            // q should not modify the panr
+            parameters
+                .set_previous_absolute_note_reference(previous_panr)
+                .set_previous_absolute_note_reference_spelling(previous_panr_spelling);
+            let check_after_q = *parameters.octave_check_after_q()
+                && *parameters.octave_entry() == OctaveEntry::Relative;
+            parameters.set_octave_check_on_next_note(previous_oconn || check_after_q);
+            format!("{prefix}q{}", modifiers.post_events())
+        }
+        false => {
+            // A chord with quarter tones cannot be repeated by 'q'
+            staff.last_chord = match bent {
+                true => None,
+                false => Some(notes.clone()),
+            };
+            // Set to first note in the chord
+            let (first, first_spelling) = first_reference.expect("At least one note is given");
+            parameters
+                .set_previous_absolute_note_reference(Some(first))
+                .set_previous_absolute_note_reference_spelling(first_spelling);
+            parameters.set_octave_check_on_next_note(false);
+            format!("{prefix}<{}>{}", chord, modifiers.post_events())
+        }
+    }
+}
+
+/// The notes and chords of each voice (from the highest voice to the lowest) as a polyphonic
+/// `<< { } \\ { } >>` fragment, or as a sequence of notes if there is a single voice
+///
+/// In relative octave entry, each voice is wrapped in its own `\relative <pitch> { }`, which
+/// continues from the last note of the same voice in the previous fragment (or starts from its
+/// own first note). A nested `\relative` does not affect the notes around it, hence the reference
+/// after the fragment is the one before it.
+fn voices_string(
+    parameters: &mut lily::LilyParameters,
+    voices: Vec<Vec<BTreeSet<MidiNote>>>,
+    bends: &mut HashMap<MidiNote, i16>,
+    staff: &mut Staff,
+) -> String {
+    if voices.len() == 1 {
+        return voices
+            .into_iter()
+            .flatten()
+            .map(|notes| notes_string(parameters, &notes, bends, staff))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+    let relative = *parameters.octave_entry() == OctaveEntry::Relative;
+    let previous_panr = parameters.previous_absolute_note_reference().cloned();
+    let previous_panr_spelling = *parameters.previous_absolute_note_reference_spelling();
+    let previous_oconn = *parameters.octave_check_on_next_note();
+    staff.voice_references.truncate(voices.len());
+    let voices: Vec<String> = voices
+        .into_iter()
+        .enumerate()
+        .map(|(index, voice)| {
+            // a repeated chord (q) repeats the last chord LilyPond read, which may be in another voice
+            staff.last_chord = None;
+            let opening = match relative {
+                true => {
+                    let (reference, spelling) = staff
+                        .voice_references
+                        .get(index)
+                        .copied()
+                        .unwrap_or_else(|| {
+                            let first = *voice
+                                .first()
+                                .and_then(|notes| notes.first())
+                                .expect("A voice has notes");
+                            lily::LilyNote::new(first, parameters).reference()
+                        });
+                    parameters
+                        .set_previous_absolute_note_reference(Some(reference))
+                        .set_previous_absolute_note_reference_spelling(spelling);
+                    parameters.set_octave_check_on_next_note(false);
+                    format!(
+                        "\\relative {} {{",
+                        lily::LilyNote::absolute_pitch(reference, spelling, parameters)
+                    )
+                }
+                false => "{".into(),
+            };
+            let notes = voice
+                .into_iter()
+                .map(|notes| notes_string(parameters, &notes, bends, staff))
+                .collect::<Vec<String>>()
+                .join(" ");
+            if let Some(reference) = parameters.previous_absolute_note_reference().copied() {
+                let voice_reference = (
+                    reference,
+                    *parameters.previous_absolute_note_reference_spelling(),
+                );
+                match staff.voice_references.get_mut(index) {
+                    Some(previous) => *previous = voice_reference,
+                    None => staff.voice_references.push(voice_reference),
+                }
+            }
+            format!("{opening} {notes} }}")
+        })
+        .collect();
+    staff.last_chord = None;
+    parameters
+        .set_previous_absolute_note_reference(previous_panr)
+        .set_previous_absolute_note_reference_spelling(previous_panr_spelling);
+    parameters.set_octave_check_on_next_note(previous_oconn);
+    format!("<< {} >>", voices.join(" \\\\ "))
+}
+
+/// A single LilyPond note, which becomes the reference for relative octave entry
+fn note_string(
+    parameters: &mut lily::LilyParameters,
+    note: MidiNote,
+    quarter_tones: Option<i16>,
+    staff: &mut Staff,
+) -> String {
+    parameters.apply_octave_check_policy(note, false);
+    let string = string_numbers(parameters, &[note])[0];
+    let modifiers = parameters.take_modifiers();
+    let lilynote = bent_lily_note(note, quarter_tones, parameters)
+        .with_string(string)
+        .with_modifiers(&modifiers);
+    let (reference, spelling) = lilynote.reference();
+    let step = lilynote.staff_step(parameters);
+    let lilynote = lilynote.to_string();
+    let prefix = staff_prefix(
+        parameters,
+        (step, step),
+        &mut staff.recent,
+        &mut staff.ottava,
+    );
+    parameters
+        .set_previous_absolute_note_reference(Some(reference))
+        .set_previous_absolute_note_reference_spelling(spelling);
+    parameters.set_octave_check_on_next_note(false);
+    format!("{prefix}{lilynote}")
+}
+
+/// Set the octave shift to a number of octaves, or shift it by an octave `up` or `down`, and
+/// report it on the status output if it changed
+fn shift_octaves(params: &mut lily::LilyParameters, value: &str) {
+    let shift = match value {
+        "up" => params.octave_shift().checked_add(1),
+        "down" => params.octave_shift().checked_sub(1),
+        _ => value.parse::<i8>().ok(),
+    };
+    match shift {
+        Some(shift) => {
+            let changed = shift != *params.octave_shift();
+            params.set_octave_shift(shift);
+            echoinfo!("Update octave-shift={:?}", shift);
+            if changed {
+                params.report_octave_shift();
+            }
+        }
+        None => echoerr!("Octave shift is not a number, up or down: {value}"),
+    }
+}
+
+/// Report an invalid note or key given on stdin, without changing any setting
+fn report_note_error(error: lily::LilypondNoteError) {
+    match error {
+        lily::LilypondNoteError::OutsideOctave(note) => {
+            echoerr!("Note is not within an octave: {note}")
+        }
+        lily::LilypondNoteError::InvalidKeyString(key) => echoerr!("Invalid key provided: {key}"),
+        lily::LilypondNoteError::InvalidNoteString(note) => {
+            echoerr!("Invalid/Unrecognized LilyPond note provided: {note}")
+        }
+        lily::LilypondNoteError::UnsupportedQuarterTone(language) => {
+            echoerr!("Language has no quarter tones: {:?}", language)
+        }
+        lily::LilypondNoteError::InvalidSpelling(note, spelling) => {
+            echoerr!("Note {note} cannot be spelled as {:?}", spelling)
+        }
+        lily::LilypondNoteError::OutsideMidiRange(quarter_tones) => {
+            echoerr!("Note is outside of the MIDI range: {quarter_tones} quarter tones")
+        }
+    }
+}
+
+/// Parse the MIDI channel dedicated to drums (1-16), or `any`
+///
+/// Returns `None` if the value is invalid.
+fn parse_drum_channel(value: &str) -> Option<Option<u8>> {
+    match value {
+        "any" => Some(None),
+        _ => match value.parse::<u8>() {
+            Ok(channel) if (1..=16).contains(&channel) => Some(Some(channel)),
+            _ => {
+                echoerr!("Drum channel is not a number between 1 and 16: {value}");
+                None
+            }
+        },
+    }
+}
+
+/// Create the LilyPond note for the given MIDI note, which may have been bent by the given number
+/// of quarter tones
+///
+/// Falls back to the note without pitch bend if the bent note cannot be expressed.
+fn bent_lily_note(
+    note: MidiNote,
+    quarter_tones: Option<i16>,
+    parameters: &lily::LilyParameters,
+) -> lily::LilyNote<'_> {
+    match quarter_tones {
+        Some(quarter_tones) => match lily::LilyNote::new_bent(note, quarter_tones, parameters) {
+            Ok(lily_note) => lily_note,
+            Err(e) => {
+                echoerr!("Ignoring pitch bend, the note cannot be expressed: {:?}", e);
+                lily::LilyNote::new(note, parameters)
+            }
+        },
+        None => lily::LilyNote::new(note, parameters),
+    }
+}
+
+/// The string numbers to write the given notes with, if a tuning is set
+///
+/// Notes which cannot be played together are written without string numbers.
+fn string_numbers(parameters: &mut lily::LilyParameters, notes: &[MidiNote]) -> Vec<Option<u8>> {
+    match parameters.assign_strings(notes) {
+        Some(strings) => strings.into_iter().map(Some).collect(),
+        None => {
+            if parameters.strings().is_some() {
+                echoerr!(
+                    "Notes cannot be played on the strings of the tuning: {:?}",
+                    notes
+                );
+            }
+            vec![None; notes.len()]
+        }
+    }
+}
+
+/// The `\clef` and `\ottava` commands to put before notes spanning the given staff steps, if
+/// the clef or the ottava changes
+///
+/// The notes are added to the recent notes used for automatic clef switching (see
+/// [lily::LilyParameters::switch_clef] and [lily::LilyParameters::ottava]).
+fn staff_prefix(
+    parameters: &mut lily::LilyParameters,
+    (lowest, highest): (i16, i16),
+    recent: &mut VecDeque<(i16, i16)>,
+    current_ottava: &mut i8,
+) -> String {
+    let mut prefix = String::new();
+    recent.push_back((lowest, highest));
+    while recent.len() > *parameters.clef_notes() as usize {
+        recent.pop_front();
+    }
+    if let Some(clef) = parameters.switch_clef(recent) {
+        let name: &str = clef.clone().try_into().expect("All clefs have a name");
+        prefix.push_str(&format!("\\clef {name} "));
+        parameters.set_clef(clef);
+        recent.clear();
+    }
+    let ottava = parameters.ottava(lowest, highest, *current_ottava);
+    if ottava != *current_ottava {
+        prefix.push_str(&format!("\\ottava #{ottava} "));
+        *current_ottava = ottava;
+    }
+    prefix
+}
+
+/// Parse the number of ledger lines beyond which to insert an `\ottava`, or `off`
+///
+/// Returns `None` if the value is invalid.
+fn parse_ottava(value: &str) -> Option<Option<u8>> {
+    match value {
+        "off" => Some(None),
+        _ => match value.parse::<u8>() {
+            Ok(lines) => Some(Some(lines)),
+            Err(_) => {
+                echoerr!("Number of ledger lines is not an unsigned number: {value}");
+                None
+            }
+        },
+    }
+}
+
+/// Take over the state of LilyPond's parser at the end of the given LilyPond source
+fn sync_context(parameters: &mut lily::LilyParameters, context: &str) {
+    match parameters.set_context_lilypond_str(context) {
+        Ok(params) => {
+            echoinfo!(
+                "Synced context: language={:?} key={:?} octave-entry={:?} fixed-reference={:?}",
+                params.language(),
+                params.key(),
+                params.octave_entry(),
+                params.fixed_reference()
+            );
+            echoinfo!(
+                "Synced context: previous-absolute-note-reference={:?}",
+                params.previous_absolute_note_reference()
+            );
+            echoinfo!(
+                "Synced context: previous-chord={:?}",
+                params.previous_chord()
+            );
+        }
+        Err(e) => match e {
+            lily::LilypondContextError::NoteOutOfRange(note) => {
+                echoerr!("Context not synced, note is outside of the MIDI range: {note}")
+            }
+            lily::LilypondContextError::InvalidKey(key) => {
+                echoerr!("Context not synced, unsupported key signature: {key}")
+            }
+            lily::LilypondContextError::InvalidLanguageString(language) => {
+                echoerr!("Context not synced, invalid language: {language}")
+            }
+        },
+    }
+}
+
+/// Define an alteration preset given as `name:subkeys`, such as `harp:2:cis,5:fis`
+///
+/// Returns whether the preset was defined.
+fn define_alteration_preset(
+    parameters: &mut lily::LilyParameters,
+    regex: &Regex,
+    definition: &str,
+) -> bool {
+    let Some((name, alterations)) = definition.split_once(':') else {
+        echoerr!("Alteration preset is not given as name:alterations: {definition}");
+        return false;
+    };
+    let Some(alterations) = parse_subkeys(regex, alterations) else {
+        echoerr!("One of the keys is not a number");
+        return false;
+    };
+    match parameters.define_alteration_preset(name.into(), alterations.into_iter().collect()) {
+        Ok(_) => {
+            echoinfo!("Defined alteration preset {name}");
+            true
+        }
+        Err(e) => {
+            echoerr!("Invalid alteration preset was given: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Define the alteration presets in the given file, with one `name:subkeys`
+/// per line (see [define_alteration_preset])
+///
+/// Empty lines and lines starting with `%` are ignored. Returns whether all
+/// presets were defined.
+fn load_alteration_presets(
+    parameters: &mut lily::LilyParameters,
+    regex: &Regex,
+    path: &str,
+) -> bool {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            echoerr!("Could not read alteration preset file {path}: {e}");
+            return false;
+        }
+    };
+    let mut success = true;
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('%'))
+    {
+        success &= define_alteration_preset(parameters, regex, line);
+    }
+    success
+}
+
 /// Parse subkeys for an input argument
 ///
 /// Returns a vector of (`note,` `value`), where the `note` is a number and the
-/// `value` is an arbitrary string with which to replace said `note`.
+/// `value` is an arbitrary string with which to replace said `note`. A subkey
+/// can also cover multiple notes (see [parse_note_pattern]), in which case the
+/// `value` is given for each of them.
 ///
-/// If any of the given `note`s cannot be parsed into a [MidiNote], then the
-/// function will return `None`.
+/// If any of the given `note`s cannot be parsed, then the function will return
+/// `None`.
 fn parse_subkeys(regex: &Regex, s: &str) -> Option<Vec<(MidiNote, String)>> {
     let mut result = Vec::new();
     for subcap in regex.captures_iter(s) {
-        let subkey: MidiNote = match subcap
-            .name("key")
-            .expect("Valid named group")
-            .as_str()
-            .parse()
-        {
-            Ok(n) => n,
-            Err(_) => {
+        let subkey = subcap.name("key").expect("Valid named group").as_str();
+        let notes = match parse_note_pattern(subkey) {
+            Some(notes) => notes,
+            None => {
                 echoerr!(
-                    "Key is not an unsigned number: {}",
-                    subcap.name("key").unwrap().as_str()
+                    "Key is not an unsigned number or a range of them: {}",
+                    subkey
                 );
                 return None;
             }
         };
-        let subvalue = subcap
+        let subvalue: String = subcap
             .name("value")
             .expect("Valid named group")
             .as_str()
             .into();
-        result.push((subkey, subvalue))
+        for note in notes {
+            result.push((note, subvalue.clone()))
+        }
     }
     Some(result)
 }
+
+/// Parse the notes covered by a subkey
+///
+/// A subkey is either a number (`60`), an inclusive range of numbers
+/// (`21-35`), or a note within an octave (0-11) followed by `o` and an
+/// inclusive range of octaves (`8o3-5`, or `8o4` for a single octave), where
+/// middle C is in octave 4.
+///
+/// Returns `None` if the subkey is invalid, or if a range is empty or exceeds
+/// the MIDI range.
+fn parse_note_pattern(s: &str) -> Option<Vec<MidiNote>> {
+    let re_range =
+        Regex::new(r"^(?:(?<class>\d+)o)?(?<from>\d+)(?:-(?<to>\d+))?$").expect("Regex is valid");
+    let caps = re_range.captures(s)?;
+    let from: u16 = caps["from"].parse().ok()?;
+    let to: u16 = match caps.name("to") {
+        Some(to) => to.as_str().parse().ok()?,
+        None => from,
+    };
+    if to > 127 {
+        return None;
+    }
+    let notes: Vec<u16> = match caps.name("class") {
+        Some(class) => {
+            let class: u16 = class.as_str().parse().ok()?;
+            if class > 11 {
+                return None;
+            }
+            (from..=to)
+                .map(|octave| (octave + 1) * 12 + class)
+                .collect()
+        }
+        None => (from..=to).collect(),
+    };
+    match !notes.is_empty() && notes.iter().all(|note| *note < 128) {
+        true => notes
+            .into_iter()
+            .map(|note| MidiNote::try_from(note).ok())
+            .collect(),
+        false => None,
+    }
+}
@@ -2,43 +2,93 @@ use portmidi::{MidiEvent, MidiMessage};
 
 use crate::MidiNote;
 
+/// Controllers of piano pedals (sustain, portamento, sostenuto, soft, legato and hold)
+const PEDAL_CONTROLLERS: std::ops::RangeInclusive<u8> = 64..=69;
+
 /// Explicity see the type of MIDI message
 #[derive(Debug)]
 pub enum MidiMessageType {
     /// A note has been pressed
-    NoteOn { note: MidiNote, velocity: u8 },
+    ///
+    /// The `channel` ranges from 1 to 16.
+    NoteOn {
+        note: MidiNote,
+        velocity: u8,
+        channel: u8,
+    },
     /// A note has been released
-    NoteOff { note: MidiNote, velocity: u8 },
+    NoteOff {
+        note: MidiNote,
+        velocity: u8,
+        channel: u8,
+    },
     /// A piano pedal has been pressed
-    PedalOn { pedal: MidiNote, value: u8 },
+    PedalOn {
+        pedal: MidiNote,
+        value: u8,
+        channel: u8,
+    },
     /// A piano pedal has been released
     ///
     /// The `value` is omitted here, because it is back to 0 when the pedal was
     /// released.
-    PedalOff { pedal: MidiNote },
+    PedalOff { pedal: MidiNote, channel: u8 },
+    /// A controller other than a pedal, such as a knob, fader or button, has
+    /// been moved
+    ControlChange {
+        controller: u8,
+        value: u8,
+        channel: u8,
+    },
+    /// A program change, such as from a patch selection switch
+    ProgramChange { program: u8, channel: u8 },
+    /// The pitch bend wheel has been moved
+    ///
+    /// The `value` is centered around 0, ranging from -8192 to 8191.
+    PitchBend { value: i16 },
     /// A midi message which has not been handled
     Unknown,
 }
 
 impl From<MidiMessage> for MidiMessageType {
     fn from(value: MidiMessage) -> Self {
-        match value.status {
+        // the lower 4 bits of the status are the channel of the message
+        let channel = (value.status & 0x0F) + 1;
+        match value.status & 0xF0 {
             144 => MidiMessageType::NoteOn {
                 note: value.data1,
                 velocity: value.data2,
+                channel,
             },
             128 => MidiMessageType::NoteOff {
                 note: value.data1,
                 velocity: value.data2,
+                channel,
+            },
+            176 if !PEDAL_CONTROLLERS.contains(&value.data1) => MidiMessageType::ControlChange {
+                controller: value.data1,
+                value: value.data2,
+                channel,
             },
             176 => match value.data2.cmp(&0) {
                 std::cmp::Ordering::Less => MidiMessageType::Unknown,
-                std::cmp::Ordering::Equal => MidiMessageType::PedalOff { pedal: value.data1 },
+                std::cmp::Ordering::Equal => MidiMessageType::PedalOff {
+                    pedal: value.data1,
+                    channel,
+                },
                 std::cmp::Ordering::Greater => MidiMessageType::PedalOn {
                     pedal: value.data1,
                     value: value.data2,
+                    channel,
                 },
             },
+            192 => MidiMessageType::ProgramChange {
+                program: value.data1,
+                channel,
+            },
+            224 => MidiMessageType::PitchBend {
+                value: ((value.data2 as i16) << 7 | value.data1 as i16) - 8192,
+            },
             _ => MidiMessageType::Unknown,
         }
     }
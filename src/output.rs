@@ -0,0 +1,53 @@
+use std::{fs::OpenOptions, io::Write, str::FromStr};
+
+use crate::{echoerr, output};
+
+/// Where the lines of an additional output stream go, such that editors can keep them apart from
+/// the notes on stdout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// Lines on stdout, prefixed by the tag and a colon (e.g. `figures: <6 4>`)
+    Tagged(String),
+    /// Lines appended to a file, which may also be a named pipe
+    File(String),
+}
+
+impl OutputTarget {
+    /// Write a line to the target
+    ///
+    /// Failing to write to a file is reported on stderr, without stopping other output.
+    pub fn write(&self, line: &str) {
+        match self {
+            OutputTarget::Tagged(tag) => output!("{tag}: {line}"),
+            OutputTarget::File(path) => {
+                let written = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut file| writeln!(file, "{line}"));
+                if let Err(e) = written {
+                    echoerr!("Could not write to {path}: {e}");
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for OutputTarget {
+    type Err = OutputTargetError;
+
+    /// Parse a target given as `tag:<name>` or `file:<path>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("tag", tag)) if !tag.is_empty() => Ok(OutputTarget::Tagged(tag.into())),
+            Some(("file", path)) if !path.is_empty() => Ok(OutputTarget::File(path.into())),
+            _ => Err(OutputTargetError::InvalidOutputTargetString(s.into())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OutputTargetError {
+    /// The string is neither `tag:<name>` nor `file:<path>`
+    InvalidOutputTargetString(String),
+}
@@ -0,0 +1,111 @@
+use getset::{Getters, MutGetters, Setters};
+
+use crate::{
+    lily::{Language, LilyNote, LilyParameters},
+    output::OutputTarget,
+    MidiNote,
+};
+
+type Definition = (String, (MidiNote, MidiNote), Option<u8>);
+
+/// A part of the keyboard whose notes are entered with their own parameters, such as chords
+/// for comping in the left part and a melody in the right part
+#[derive(Debug, Getters, MutGetters, Setters)]
+pub struct Zone {
+    /// name by which the zone is selected, and the default tag of its output
+    #[getset(get = "pub")]
+    name: String,
+    /// lowest and highest notes of the zone (inclusive, at concert pitch)
+    #[getset(get = "pub")]
+    range: (MidiNote, MidiNote),
+    /// MIDI channel the zone is restricted to, or `None` for all channels
+    #[getset(get = "pub")]
+    channel: Option<u8>,
+    /// where the notes of the zone go
+    #[getset(get = "pub", set = "pub")]
+    output: OutputTarget,
+    /// parameters for entering the notes of the zone
+    #[getset(get = "pub", get_mut = "pub")]
+    parameters: LilyParameters,
+}
+
+impl Zone {
+    /// Create a zone from a definition like `<name>:<lowest>:<highest>[:<channel>]`, with
+    /// LilyPond notes in absolute octave entry (e.g. `left:a,,,:b` or `right:c':c'''''`)
+    ///
+    /// The zone starts out with a copy of the given parameters, and its output is tagged with
+    /// its name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the definition is not valid.
+    pub fn new(definition: &str, parameters: &LilyParameters) -> Result<Self, ZoneError> {
+        let (name, range, channel) = parse_definition(definition, parameters.language())?;
+        Ok(Zone {
+            output: OutputTarget::Tagged(name.clone()),
+            name,
+            range,
+            channel,
+            parameters: parameters.clone(),
+        })
+    }
+
+    /// Change the range and channel of the zone from a definition (see [Zone::new]), keeping
+    /// its parameters and output
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the definition is not valid, in which case the
+    /// zone is not changed.
+    pub fn redefine(&mut self, definition: &str) -> Result<&mut Self, ZoneError> {
+        let (_, range, channel) = parse_definition(definition, self.parameters.language())?;
+        self.range = range;
+        self.channel = channel;
+        Ok(self)
+    }
+
+    /// Whether the given note on the given MIDI channel is played in the zone
+    pub fn contains(&self, note: MidiNote, channel: u8) -> bool {
+        (self.range.0..=self.range.1).contains(&note)
+            && self
+                .channel
+                .is_none_or(|zone_channel| zone_channel == channel)
+    }
+}
+
+/// Parse a zone definition into the name, range and channel of the zone
+fn parse_definition(definition: &str, language: &Language) -> Result<Definition, ZoneError> {
+    let invalid = || ZoneError::InvalidZoneString(definition.into());
+    let mut parts = definition.split(':');
+    let name = parts
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(invalid)?;
+    let mut note = || -> Result<MidiNote, ZoneError> {
+        let note = parts.next().ok_or_else(invalid)?;
+        LilyNote::from_lilypond_str(note, language)
+            .map(|note| *note.note())
+            .map_err(|_| invalid())
+    };
+    let (low, high) = (note()?, note()?);
+    let channel = match parts.next() {
+        Some(channel) => Some(
+            channel
+                .parse::<u8>()
+                .ok()
+                .filter(|channel| (1..=16).contains(channel))
+                .ok_or_else(invalid)?,
+        ),
+        None => None,
+    };
+    if low > high || parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok((name.into(), (low, high), channel))
+}
+
+#[derive(Debug)]
+pub enum ZoneError {
+    /// The string is not a valid zone definition
+    InvalidZoneString(String),
+}